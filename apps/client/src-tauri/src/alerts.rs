@@ -0,0 +1,327 @@
+//! Desktop notifications and alert tones for what a dispatcher must not miss:
+//! new high priority calls, escalations, unit emergencies and units of the
+//! dispatcher's position being assigned to a call.
+//!
+//! Alerts are worked out here from the server's messages, before they change
+//! the local state, so a call can be compared with what we held before. Each
+//! one is shown as a native notification and sent to the windows as an `alert`
+//! event, whose `tone` (if any) the frontend plays.
+//!
+//! The user's rules live under `alerts` in the settings store. Muting silences
+//! tones and quiet hours hold back everything, except emergencies, which
+//! always get through.
+
+use std::collections::HashMap;
+
+use chrono::NaiveTime;
+use serde::{Deserialize, Serialize};
+use shared_types::id::UnitId;
+use shared_types::incident::{IncidentCall, Priority};
+use shared_types::ProtocolMessage;
+use tauri::{AppHandle, Emitter};
+use tauri_plugin_notification::NotificationExt;
+use tauri_plugin_store::StoreExt;
+
+use crate::local_state::LocalState;
+
+const SETTINGS_PATH: &str = "settings.json";
+const SETTINGS_KEY: &str = "alerts";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum AlertKind {
+    /// A call opened at, or raised to, high priority
+    HighPriorityCall,
+    /// A pending call waited past its priority's threshold
+    CallEscalated,
+    Emergency,
+    /// A unit of the dispatcher's position was assigned to a call
+    Assignment,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AlertPreference {
+    /// Whether to show a desktop notification
+    pub notify: bool,
+    /// Name of the tone to play, if any
+    pub tone: Option<String>,
+}
+
+impl Default for AlertPreference {
+    fn default() -> Self {
+        Self { notify: true, tone: Some("chime".to_string()) }
+    }
+}
+
+/// Quiet hours in local time, e.g. `22:00` to `06:00`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QuietHours {
+    pub start: String,
+    pub end: String,
+}
+
+impl QuietHours {
+    fn contains(&self, time: NaiveTime) -> bool {
+        let parse = |s: &str| NaiveTime::parse_from_str(s, "%H:%M").ok();
+        let (Some(start), Some(end)) = (parse(&self.start), parse(&self.end)) else {
+            return false;
+        };
+        if start <= end {
+            start <= time && time < end
+        } else {
+            // Runs past midnight
+            time >= start || time < end
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AlertRules {
+    /// Silences tones; notifications are still shown
+    pub muted: bool,
+    pub quiet_hours: Option<QuietHours>,
+    /// Kinds missing here use their defaults
+    pub kinds: HashMap<AlertKind, AlertPreference>,
+    /// Units this dispatcher's position looks after
+    pub position_units: Vec<UnitId>,
+}
+
+impl AlertRules {
+    fn load(app: &AppHandle) -> Self {
+        app.store(SETTINGS_PATH)
+            .ok()
+            .and_then(|store| store.get(SETTINGS_KEY))
+            .and_then(|value| serde_json::from_value(value).ok())
+            .unwrap_or_default()
+    }
+
+    fn preference(&self, kind: AlertKind) -> AlertPreference {
+        self.kinds.get(&kind).cloned().unwrap_or_else(|| match kind {
+            AlertKind::Emergency => AlertPreference { notify: true, tone: Some("alarm".to_string()) },
+            AlertKind::Assignment => AlertPreference { notify: true, tone: Some("beep".to_string()) },
+            _ => AlertPreference::default(),
+        })
+    }
+}
+
+/// Payload of the `alert` event.
+#[derive(Debug, Clone, Serialize)]
+pub struct Alert {
+    pub kind: AlertKind,
+    pub title: String,
+    pub body: String,
+    /// Tone to play, after the user's rules
+    pub tone: Option<String>,
+}
+
+/// An alert before the user's rules are applied.
+struct Raised {
+    kind: AlertKind,
+    title: String,
+    body: String,
+    /// Whether it may make a sound at all
+    audible: bool,
+}
+
+impl Raised {
+    fn new(kind: AlertKind, title: String, body: String) -> Self {
+        Self { kind, title, body, audible: true }
+    }
+}
+
+fn describe(call: &IncidentCall) -> String {
+    let location = call.location.name.as_deref().or(call.location.detail.as_deref());
+    match location {
+        Some(location) => format!("{} at {}", call.call_nature.description, location),
+        None => call.call_nature.description.clone(),
+    }
+}
+
+fn detect(message: &ProtocolMessage, local: &LocalState, rules: &AlertRules) -> Vec<Raised> {
+    let mut raised = Vec::new();
+    match message {
+        // Only broadcasts: a `Call` reply may be for an old call, another
+        // event, or one this dispatcher just opened
+        ProtocolMessage::CallUpdated { call } => {
+            if call.times.cleared.is_some() || !local.is_current(call.event_id) {
+                return raised;
+            }
+            let previous = local.call(call.id);
+            let was_high = previous.as_ref().is_some_and(|p| p.priority == Priority::High);
+            if call.priority == Priority::High && !was_high {
+                let title = match previous {
+                    Some(_) => format!("Call {} raised to high priority", call.incident_number),
+                    None => format!("New high priority call {}", call.incident_number),
+                };
+                raised.push(Raised::new(AlertKind::HighPriorityCall, title, describe(call)));
+            }
+
+            let assigned_before: Vec<UnitId> = previous
+                .iter()
+                .flat_map(|p| p.units_assigned.iter().map(|a| a.unit.id))
+                .collect();
+            for assigned in &call.units_assigned {
+                let unit = &assigned.unit;
+                if rules.position_units.contains(&unit.id) && !assigned_before.contains(&unit.id) {
+                    raised.push(Raised::new(
+                        AlertKind::Assignment,
+                        format!("{} assigned to {}", unit.name, call.incident_number),
+                        describe(call),
+                    ));
+                }
+            }
+        }
+        ProtocolMessage::CallEscalated { id, waiting_secs, .. } => {
+            if let Some(call) = local.call(*id) {
+                raised.push(Raised::new(
+                    AlertKind::CallEscalated,
+                    format!("Call {} waiting {} min", call.incident_number, waiting_secs / 60),
+                    describe(&call),
+                ));
+            }
+        }
+        ProtocolMessage::EmergencyAlert { unit, location, audible, .. } => raised.push(Raised {
+            kind: AlertKind::Emergency,
+            title: format!("EMERGENCY: {}", unit.name),
+            body: location.clone(),
+            audible: *audible,
+        }),
+        _ => {}
+    }
+    raised
+}
+
+/// Raises any alerts `message` calls for. Call before applying it to `local`.
+pub fn check(app: &AppHandle, local: &LocalState, message: &ProtocolMessage) {
+    if !matches!(
+        message,
+        ProtocolMessage::CallUpdated { .. }
+            | ProtocolMessage::CallEscalated { .. }
+            | ProtocolMessage::EmergencyAlert { .. }
+    ) {
+        return;
+    }
+
+    let rules = AlertRules::load(app);
+    let quiet = rules
+        .quiet_hours
+        .as_ref()
+        .is_some_and(|hours| hours.contains(chrono::Local::now().time()));
+    for raised in detect(message, local, &rules) {
+        let urgent = raised.kind == AlertKind::Emergency;
+        if quiet && !urgent {
+            continue;
+        }
+        let preference = rules.preference(raised.kind);
+        if preference.notify || urgent {
+            let shown = app
+                .notification()
+                .builder()
+                .title(&raised.title)
+                .body(&raised.body)
+                .show();
+            if let Err(e) = shown {
+                log!("Failed to show notification: {}", e);
+            }
+        }
+
+        let silenced = !raised.audible || (rules.muted && !urgent);
+        let alert = Alert {
+            kind: raised.kind,
+            title: raised.title,
+            body: raised.body,
+            tone: if silenced { None } else { preference.tone },
+        };
+        app.emit("alert", alert).unwrap_or_default();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::local_state::tests::{call, event, EVENT, OTHER_EVENT};
+
+    fn hours(start: &str, end: &str) -> QuietHours {
+        QuietHours { start: start.to_string(), end: end.to_string() }
+    }
+
+    fn at(time: &str) -> NaiveTime {
+        NaiveTime::parse_from_str(time, "%H:%M").unwrap()
+    }
+
+    fn kinds(message: &ProtocolMessage, local: &LocalState) -> Vec<AlertKind> {
+        detect(message, local, &AlertRules::default()).iter().map(|raised| raised.kind).collect()
+    }
+
+    fn local_state() -> LocalState {
+        let local = LocalState::default();
+        local.apply(&ProtocolMessage::ActiveEvent { event: event(EVENT) });
+        local
+    }
+
+    #[test]
+    fn quiet_hours_within_a_day() {
+        let quiet = hours("13:00", "14:30");
+        assert!(quiet.contains(at("13:00")));
+        assert!(quiet.contains(at("14:29")));
+        assert!(!quiet.contains(at("14:30")));
+        assert!(!quiet.contains(at("12:59")));
+    }
+
+    #[test]
+    fn quiet_hours_past_midnight() {
+        let quiet = hours("22:00", "06:00");
+        assert!(quiet.contains(at("22:00")));
+        assert!(quiet.contains(at("23:59")));
+        assert!(quiet.contains(at("00:00")));
+        assert!(quiet.contains(at("05:59")));
+        assert!(!quiet.contains(at("06:00")));
+        assert!(!quiet.contains(at("12:00")));
+    }
+
+    #[test]
+    fn unreadable_quiet_hours_hold_nothing_back() {
+        assert!(!hours("10pm", "06:00").contains(at("23:00")));
+        assert!(!hours("22:00", "").contains(at("23:00")));
+        assert!(!hours("25:00", "06:00").contains(at("01:00")));
+    }
+
+    #[test]
+    fn new_and_raised_high_priority_calls_alert() {
+        let local = local_state();
+        let new_call = ProtocolMessage::CallUpdated { call: call(1, EVENT, "high") };
+        assert_eq!(kinds(&new_call, &local), vec![AlertKind::HighPriorityCall]);
+
+        local.apply(&ProtocolMessage::CallUpdated { call: call(2, EVENT, "low") });
+        let raised = ProtocolMessage::CallUpdated { call: call(2, EVENT, "high") };
+        assert_eq!(kinds(&raised, &local), vec![AlertKind::HighPriorityCall]);
+    }
+
+    #[test]
+    fn calls_already_high_do_not_alert_again() {
+        let local = local_state();
+        let message = ProtocolMessage::CallUpdated { call: call(1, EVENT, "high") };
+        local.apply(&message);
+
+        assert!(kinds(&message, &local).is_empty());
+    }
+
+    #[test]
+    fn cleared_calls_do_not_alert() {
+        let local = local_state();
+        let mut cleared = call(1, EVENT, "high");
+        cleared.times.cleared = Some(cleared.times.received);
+
+        assert!(kinds(&ProtocolMessage::CallUpdated { call: cleared }, &local).is_empty());
+    }
+
+    #[test]
+    fn calls_for_other_events_do_not_alert() {
+        let local = local_state();
+        let message = ProtocolMessage::CallUpdated { call: call(1, OTHER_EVENT, "high") };
+
+        assert!(kinds(&message, &local).is_empty());
+    }
+}
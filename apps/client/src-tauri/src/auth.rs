@@ -0,0 +1,174 @@
+//! Cloudflare Access (WARP) credentials for the WebSocket handshake.
+//!
+//! The dispatch server only upgrades connections that carry a valid Access JWT
+//! as a bearer token. The token is fetched through WARP, cached until shortly
+//! before its `exp`, and fetched again whenever the server rejects it.
+
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use serde::{Deserialize, Serialize};
+use tokio_tungstenite::connect_async;
+use tokio_tungstenite::tungstenite::client::IntoClientRequest;
+use tokio_tungstenite::tungstenite::http::{header, HeaderValue, StatusCode};
+use tokio_tungstenite::tungstenite::Error as WsError;
+
+use crate::WsStream;
+
+/// Refresh tokens this long before they expire so a handshake never races expiry.
+const EXPIRY_MARGIN_SECS: i64 = 60;
+
+/// Authentication state shown to the user, emitted as `ws-auth`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum AuthStatus {
+    Authenticating,
+    Authenticated,
+    /// The server rejected our token; a fresh one is fetched before retrying
+    Expired,
+    /// No token could be obtained, usually because WARP is not connected
+    Unauthenticated,
+    /// Signed in, but the account is disabled on the server
+    Forbidden,
+}
+
+/// Why a handshake failed.
+#[derive(Debug)]
+pub enum HandshakeError {
+    /// 401: the token was missing, expired or invalid
+    Unauthorized,
+    /// 403: the token is valid but the account may not connect
+    Forbidden,
+    /// Anything else, including the server being unreachable
+    Network(WsError),
+}
+
+impl std::fmt::Display for HandshakeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            HandshakeError::Unauthorized => write!(f, "server rejected our credentials"),
+            HandshakeError::Forbidden => write!(f, "account is not allowed to connect"),
+            HandshakeError::Network(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct Claims {
+    exp: Option<i64>,
+}
+
+/// Reads the `exp` claim without verifying the token; the server does that.
+fn expires_at(token: &str) -> Option<i64> {
+    let payload = token.split('.').nth(1)?;
+    let claims: Claims = serde_json::from_slice(&URL_SAFE_NO_PAD.decode(payload).ok()?).ok()?;
+    claims.exp
+}
+
+struct Token {
+    value: String,
+    expires_at: Option<i64>,
+}
+
+impl Token {
+    fn is_expired(&self) -> bool {
+        let now = chrono::Utc::now().timestamp();
+        self.expires_at.is_some_and(|exp| now + EXPIRY_MARGIN_SECS >= exp)
+    }
+}
+
+/// Caches the Access token for one server.
+pub struct Credentials {
+    server_url: String,
+    token: Option<Token>,
+}
+
+impl Credentials {
+    pub fn new(server_url: impl Into<String>) -> Self {
+        Self {
+            server_url: server_url.into(),
+            token: None,
+        }
+    }
+
+    /// The cached token, fetching a new one if there is none or it is about to expire.
+    pub async fn token(&mut self) -> Option<String> {
+        if self.token.as_ref().is_none_or(Token::is_expired) {
+            self.token = get_warp_token(&self.server_url).await.map(|value| Token {
+                expires_at: expires_at(&value),
+                value,
+            });
+        }
+        self.token.as_ref().map(|token| token.value.clone())
+    }
+
+    /// Forgets the cached token after the server rejects it.
+    pub fn invalidate(&mut self) {
+        self.token = None;
+    }
+}
+
+async fn get_warp_token(server_url: &str) -> Option<String> {
+    let client = reqwest::Client::new();
+
+    log!("Checking WARP status for: {}", server_url);
+
+    // Try to make a request through WARP
+    match client.head(server_url)
+        .send()
+        .await {
+            Ok(response) => {
+                // Check for WARP token in headers
+                if let Some(token) = response.headers().get("CF-Access-Jwt-Assertion") {
+                    log!("Found WARP token");
+                    return Some(token.to_str().unwrap_or_default().to_string());
+                }
+
+                // Also check Cookies for CF_Authorization
+                if let Some(cookies) = response.headers().get("set-cookie") {
+                    if let Ok(cookie_str) = cookies.to_str() {
+                        if cookie_str.contains("CF_Authorization=") {
+                            log!("Found CF_Authorization cookie");
+                            if let Some(token) = cookie_str
+                                .split(';')
+                                .find(|s| s.trim().starts_with("CF_Authorization="))
+                                .and_then(|s| s.split('=').nth(1)) {
+                                return Some(token.to_string());
+                            }
+                        }
+                    }
+                }
+            }
+            Err(e) => {
+                log!("Failed to get WARP token: {}", e);
+            }
+        }
+
+    log!("No WARP token found");
+    None
+}
+
+/// Opens the WebSocket, presenting `token` as a bearer token (and as the
+/// Access cookie, for deployments where Cloudflare sits in front of the server).
+pub async fn connect(ws_url: &str, token: Option<&str>) -> Result<WsStream, HandshakeError> {
+    let mut request = ws_url.into_client_request().map_err(HandshakeError::Network)?;
+
+    if let Some(token) = token {
+        let headers = request.headers_mut();
+        if let Ok(value) = HeaderValue::from_str(&format!("Bearer {}", token)) {
+            headers.insert(header::AUTHORIZATION, value);
+        }
+        if let Ok(value) = HeaderValue::from_str(&format!("CF_Authorization={}", token)) {
+            headers.insert(header::COOKIE, value);
+        }
+    }
+
+    match connect_async(request).await {
+        Ok((ws_stream, _)) => Ok(ws_stream),
+        Err(WsError::Http(response)) if response.status() == StatusCode::UNAUTHORIZED => {
+            Err(HandshakeError::Unauthorized)
+        }
+        Err(WsError::Http(response)) if response.status() == StatusCode::FORBIDDEN => {
+            Err(HandshakeError::Forbidden)
+        }
+        Err(e) => Err(HandshakeError::Network(e)),
+    }
+}
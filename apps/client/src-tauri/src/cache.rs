@@ -0,0 +1,152 @@
+//! An encrypted copy of the local state on disk, so dispatchers can still see
+//! the open calls when the venue network goes down, even across a restart.
+//!
+//! The state is written a moment after it changes, but only while it is in
+//! step with the server. It is sealed with AES-256-GCM under a key kept in the
+//! operating system's keychain, so a copied cache file reveals nothing about
+//! the calls in it.
+
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+
+use aes_gcm::aead::{Aead, AeadCore, KeyInit, OsRng};
+use aes_gcm::{Aes256Gcm, Key, Nonce};
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter, Manager};
+use tokio::sync::watch;
+
+use crate::local_state::{LocalState, Snapshot};
+use crate::profiles::ServerProfile;
+
+const KEYRING_SERVICE: &str = "rover-dispatch";
+const KEYRING_USER: &str = "local-cache";
+const CACHE_FILE: &str = "state-cache.bin";
+/// How long to let changes pile up before writing them out.
+const SAVE_DELAY: Duration = Duration::from_secs(2);
+const NONCE_LEN: usize = 12;
+
+#[derive(Serialize, Deserialize)]
+struct CachedState {
+    /// The server the state came from
+    server_url: String,
+    /// Unix time in milliseconds
+    saved_at: i64,
+    snapshot: Snapshot,
+}
+
+pub struct Cache {
+    path: PathBuf,
+    cipher: Aes256Gcm,
+}
+
+impl Cache {
+    pub fn open(app: &AppHandle) -> Result<Self, String> {
+        let dir = app.path().app_data_dir().map_err(|e| e.to_string())?;
+        std::fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+        Ok(Self {
+            path: dir.join(CACHE_FILE),
+            cipher: Aes256Gcm::new(&load_key()?),
+        })
+    }
+
+    /// Reads the state cached from `server_url` and when it was saved. A cache
+    /// from another server, or one we can no longer decrypt, is ignored.
+    pub fn load(&self, server_url: &str) -> Option<(Snapshot, i64)> {
+        let sealed = std::fs::read(&self.path).ok()?;
+        if sealed.len() < NONCE_LEN {
+            return None;
+        }
+        let (nonce, ciphertext) = sealed.split_at(NONCE_LEN);
+        let plaintext = match self.cipher.decrypt(Nonce::from_slice(nonce), ciphertext) {
+            Ok(plaintext) => plaintext,
+            Err(_) => {
+                log!("Discarding offline cache that could not be decrypted");
+                return None;
+            }
+        };
+        let cached: CachedState = serde_json::from_slice(&plaintext).ok()?;
+        (cached.server_url == server_url).then_some((cached.snapshot, cached.saved_at))
+    }
+
+    fn save(&self, server_url: &str, snapshot: Snapshot) -> Result<(), String> {
+        let cached = CachedState {
+            server_url: server_url.to_string(),
+            saved_at: chrono::Utc::now().timestamp_millis(),
+            snapshot,
+        };
+        let plaintext = serde_json::to_vec(&cached).map_err(|e| e.to_string())?;
+        let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+        let ciphertext = self
+            .cipher
+            .encrypt(&nonce, plaintext.as_slice())
+            .map_err(|e| e.to_string())?;
+
+        // Write beside the old cache and swap, so a crash never leaves half a file
+        let mut sealed = nonce.to_vec();
+        sealed.extend_from_slice(&ciphertext);
+        let partial = self.path.with_extension("partial");
+        std::fs::write(&partial, sealed).map_err(|e| e.to_string())?;
+        std::fs::rename(&partial, &self.path).map_err(|e| e.to_string())
+    }
+}
+
+/// Fetches the cache key from the keychain, creating it on first run.
+fn load_key() -> Result<Key<Aes256Gcm>, String> {
+    let entry = keyring::Entry::new(KEYRING_SERVICE, KEYRING_USER).map_err(|e| e.to_string())?;
+    match entry.get_secret() {
+        Ok(secret) if secret.len() == 32 => return Ok(*Key::<Aes256Gcm>::from_slice(&secret)),
+        // A key of the wrong size can't have been ours; replace it
+        Ok(_) | Err(keyring::Error::NoEntry) => {}
+        Err(e) => return Err(e.to_string()),
+    }
+    let key = Aes256Gcm::generate_key(&mut OsRng);
+    entry.set_secret(&key).map_err(|e| e.to_string())?;
+    Ok(key)
+}
+
+/// Restores the state cached from the active server, then keeps the cache up
+/// to date in the background. Without a keychain the client runs uncached.
+pub fn start(app: &AppHandle, local: Arc<LocalState>, mut profile_rx: watch::Receiver<ServerProfile>) {
+    let cache = match Cache::open(app) {
+        Ok(cache) => cache,
+        Err(e) => {
+            log!("Offline cache unavailable: {}", e);
+            return;
+        }
+    };
+    if let Some((snapshot, saved_at)) = cache.load(&profile_rx.borrow_and_update().server_url) {
+        log!("Restored {} calls from the offline cache", snapshot.calls.len());
+        local.restore(snapshot, saved_at);
+    }
+    app.manage(cache);
+
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        let cache = app.state::<Cache>();
+        loop {
+            local.changed().await;
+            tokio::time::sleep(SAVE_DELAY).await;
+            // After a switch the state may be either server's until the
+            // connection loop has swapped it, so leave it for the next change
+            if profile_rx.has_changed().unwrap_or(true) {
+                profile_rx.borrow_and_update();
+                continue;
+            }
+            let Some(snapshot) = local.live_snapshot() else {
+                continue;
+            };
+            let server_url = profile_rx.borrow().server_url.clone();
+            if let Err(e) = cache.save(&server_url, snapshot) {
+                log!("Failed to save offline cache: {}", e);
+            }
+        }
+    });
+}
+
+/// Swaps the local state for what was cached from `server_url`, or clears it.
+/// Call after switching servers, before connecting to the new one.
+pub fn switch_server(app: &AppHandle, local: &LocalState, server_url: &str) {
+    let cached = app.try_state::<Cache>().and_then(|cache| cache.load(server_url));
+    app.emit("state-change", local.replace(cached)).unwrap_or_default();
+}
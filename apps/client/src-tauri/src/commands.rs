@@ -0,0 +1,390 @@
+//! Tauri commands for dispatch operations. Each sends one request to the server
+//! and waits for its correlated reply, so the frontend gets a typed result or a
+//! [`CommandError`] instead of watching `ws-message` for an answer.
+//!
+//! Requests that the server answers only with a broadcast (unit status changes,
+//! emergencies, roll calls, ...) resolve once the server acknowledges them; the
+//! broadcast still arrives as a `ws-message`.
+//!
+//! Commands that change calls or units go through the [`Outbox`] and resolve
+//! with a [`Delivery`], which is `queued` when the client is offline.
+
+use shared_types::accountability::RollCall;
+use shared_types::event::{Event, NewEvent};
+use shared_types::id::{EventId, IncidentId, LocationId, RollCallId, UnitId};
+use shared_types::incident::{CallNature, CallUpdate, IncidentCall, NewIncidentCall, Unit, UnitStatus};
+use shared_types::location::{Location, NewLocation};
+use shared_types::map::{MapLayer, Position};
+use shared_types::routing::{LocationLink, NewLocationLink, Route, UnitRecommendation};
+use shared_types::ProtocolMessage;
+use tauri::State;
+
+use crate::local_state::Snapshot;
+use crate::outbox::{Delivery, Outbox};
+use crate::requests::CommandError;
+use crate::WebSocketState;
+
+/// For requests whose only reply is an acknowledgement.
+fn expect_ack(reply: ProtocolMessage) -> Result<(), CommandError> {
+    match reply {
+        ProtocolMessage::Ack => Ok(()),
+        other => Err(CommandError::unexpected(&other)),
+    }
+}
+
+/// What the client currently holds, for a window catching up on `state-change`.
+#[tauri::command]
+pub fn get_local_state(state: State<'_, WebSocketState>) -> Snapshot {
+    state.local.snapshot()
+}
+
+#[tauri::command]
+pub async fn send_ping(state: State<'_, WebSocketState>) -> Result<(), CommandError> {
+    expect_ack(state.request(ProtocolMessage::Ping).await?)
+}
+
+#[tauri::command]
+pub async fn list_events(state: State<'_, WebSocketState>) -> Result<Vec<Event>, CommandError> {
+    match state.request(ProtocolMessage::ListEvents).await? {
+        ProtocolMessage::Events { events } => Ok(events),
+        other => Err(CommandError::unexpected(&other)),
+    }
+}
+
+#[tauri::command]
+pub async fn create_event(state: State<'_, WebSocketState>, event: NewEvent) -> Result<Event, CommandError> {
+    match state.request(ProtocolMessage::CreateEvent { event }).await? {
+        ProtocolMessage::Event { event } => Ok(event),
+        other => Err(CommandError::unexpected(&other)),
+    }
+}
+
+#[tauri::command]
+pub async fn set_active_event(state: State<'_, WebSocketState>, event_id: EventId) -> Result<Event, CommandError> {
+    match state.request(ProtocolMessage::SetActiveEvent { event_id }).await? {
+        ProtocolMessage::ActiveEvent { event } => Ok(event),
+        other => Err(CommandError::unexpected(&other)),
+    }
+}
+
+#[tauri::command]
+pub async fn get_active_calls(state: State<'_, WebSocketState>) -> Result<Vec<IncidentCall>, CommandError> {
+    match state.request(ProtocolMessage::GetActiveCalls).await? {
+        ProtocolMessage::ActiveCalls { calls } => Ok(calls),
+        other => Err(CommandError::unexpected(&other)),
+    }
+}
+
+#[tauri::command]
+pub async fn get_event_calls(
+    state: State<'_, WebSocketState>,
+    event_id: EventId,
+) -> Result<Vec<IncidentCall>, CommandError> {
+    match state.request(ProtocolMessage::GetEventCalls { event_id }).await? {
+        ProtocolMessage::EventCalls { calls, .. } => Ok(calls),
+        other => Err(CommandError::unexpected(&other)),
+    }
+}
+
+/// `date` is a calendar day of the active event, e.g. `2024-11-03`.
+#[tauri::command]
+pub async fn get_calls_on_day(
+    state: State<'_, WebSocketState>,
+    date: String,
+) -> Result<Vec<IncidentCall>, CommandError> {
+    let date = shared_types::wire::date::deserialize(serde_json::Value::String(date))
+        .map_err(|e| CommandError::InvalidArgument(format!("Invalid date: {}", e)))?;
+    match state.request(ProtocolMessage::GetCallsOnDay { date }).await? {
+        ProtocolMessage::CallsOnDay { calls, .. } => Ok(calls),
+        other => Err(CommandError::unexpected(&other)),
+    }
+}
+
+#[tauri::command]
+pub async fn get_call(state: State<'_, WebSocketState>, id: IncidentId) -> Result<IncidentCall, CommandError> {
+    match state.request(ProtocolMessage::GetCall { id }).await? {
+        ProtocolMessage::Call { call } => Ok(call),
+        other => Err(CommandError::unexpected(&other)),
+    }
+}
+
+#[tauri::command]
+pub async fn create_call(
+    state: State<'_, WebSocketState>,
+    outbox: State<'_, Outbox>,
+    call: NewIncidentCall,
+) -> Result<Delivery<IncidentCall>, CommandError> {
+    let delivery = outbox.deliver(&state, ProtocolMessage::CreateCall { call }).await?;
+    delivery.try_map(|reply| match reply {
+        ProtocolMessage::Call { call } => Ok(call),
+        other => Err(CommandError::unexpected(&other)),
+    })
+}
+
+#[tauri::command]
+pub async fn update_call(
+    state: State<'_, WebSocketState>,
+    outbox: State<'_, Outbox>,
+    id: IncidentId,
+    update: CallUpdate,
+) -> Result<Delivery<IncidentCall>, CommandError> {
+    let delivery = outbox.deliver(&state, ProtocolMessage::UpdateCall { id, update }).await?;
+    delivery.try_map(|reply| match reply {
+        ProtocolMessage::Call { call } => Ok(call),
+        other => Err(CommandError::unexpected(&other)),
+    })
+}
+
+#[tauri::command]
+pub async fn add_note(
+    state: State<'_, WebSocketState>,
+    outbox: State<'_, Outbox>,
+    incident_id: IncidentId,
+    content: String,
+) -> Result<Delivery<IncidentCall>, CommandError> {
+    let delivery = outbox.deliver(&state, ProtocolMessage::AddNote { incident_id, content }).await?;
+    delivery.try_map(|reply| match reply {
+        ProtocolMessage::Call { call } => Ok(call),
+        other => Err(CommandError::unexpected(&other)),
+    })
+}
+
+#[tauri::command]
+pub async fn get_call_natures(state: State<'_, WebSocketState>) -> Result<Vec<CallNature>, CommandError> {
+    match state.request(ProtocolMessage::GetCallNatures).await? {
+        ProtocolMessage::CallNatures { natures } => Ok(natures),
+        other => Err(CommandError::unexpected(&other)),
+    }
+}
+
+#[tauri::command]
+pub async fn get_pending_queue(state: State<'_, WebSocketState>) -> Result<Vec<IncidentCall>, CommandError> {
+    match state.request(ProtocolMessage::GetPendingQueue).await? {
+        ProtocolMessage::PendingQueue { calls } => Ok(calls),
+        other => Err(CommandError::unexpected(&other)),
+    }
+}
+
+#[tauri::command]
+pub async fn recommend_units(
+    state: State<'_, WebSocketState>,
+    incident_id: IncidentId,
+    accessible_only: bool,
+) -> Result<Vec<UnitRecommendation>, CommandError> {
+    match state.request(ProtocolMessage::RecommendUnits { incident_id, accessible_only }).await? {
+        ProtocolMessage::UnitRecommendations { recommendations, .. } => Ok(recommendations),
+        other => Err(CommandError::unexpected(&other)),
+    }
+}
+
+#[tauri::command]
+pub async fn dispatch_unit(
+    state: State<'_, WebSocketState>,
+    outbox: State<'_, Outbox>,
+    incident_id: IncidentId,
+    unit_id: UnitId,
+) -> Result<Delivery<()>, CommandError> {
+    outbox.deliver(&state, ProtocolMessage::DispatchUnit { incident_id, unit_id }).await?.try_map(expect_ack)
+}
+
+#[tauri::command]
+pub async fn get_units(state: State<'_, WebSocketState>) -> Result<Vec<Unit>, CommandError> {
+    match state.request(ProtocolMessage::GetUnits).await? {
+        ProtocolMessage::Units { units } => Ok(units),
+        other => Err(CommandError::unexpected(&other)),
+    }
+}
+
+#[tauri::command]
+pub async fn update_unit_status(
+    state: State<'_, WebSocketState>,
+    outbox: State<'_, Outbox>,
+    unit_id: UnitId,
+    status: UnitStatus,
+    location: Option<String>,
+) -> Result<Delivery<()>, CommandError> {
+    let message = ProtocolMessage::UpdateUnitStatus { unit_id, status, location };
+    outbox.deliver(&state, message).await?.try_map(expect_ack)
+}
+
+#[tauri::command]
+pub async fn check_in_unit(
+    state: State<'_, WebSocketState>,
+    outbox: State<'_, Outbox>,
+    unit_id: UnitId,
+) -> Result<Delivery<()>, CommandError> {
+    outbox.deliver(&state, ProtocolMessage::CheckInUnit { unit_id }).await?.try_map(expect_ack)
+}
+
+#[tauri::command]
+pub async fn update_unit_location(
+    state: State<'_, WebSocketState>,
+    outbox: State<'_, Outbox>,
+    unit_id: UnitId,
+    location_id: LocationId,
+) -> Result<Delivery<()>, CommandError> {
+    let message = ProtocolMessage::UpdateUnitLocation { unit_id, location_id };
+    outbox.deliver(&state, message).await?.try_map(expect_ack)
+}
+
+#[tauri::command]
+pub async fn update_unit_position(
+    state: State<'_, WebSocketState>,
+    unit_id: UnitId,
+    position: Position,
+) -> Result<(), CommandError> {
+    expect_ack(state.request(ProtocolMessage::UpdateUnitPosition { unit_id, position }).await?)
+}
+
+#[tauri::command]
+pub async fn activate_emergency(
+    state: State<'_, WebSocketState>,
+    unit_id: UnitId,
+    location: Option<String>,
+) -> Result<(), CommandError> {
+    expect_ack(state.request(ProtocolMessage::ActivateEmergency { unit_id, location }).await?)
+}
+
+#[tauri::command]
+pub async fn acknowledge_emergency(state: State<'_, WebSocketState>, unit_id: UnitId) -> Result<(), CommandError> {
+    expect_ack(state.request(ProtocolMessage::AcknowledgeEmergency { unit_id }).await?)
+}
+
+#[tauri::command]
+pub async fn clear_emergency(state: State<'_, WebSocketState>, unit_id: UnitId) -> Result<(), CommandError> {
+    expect_ack(state.request(ProtocolMessage::ClearEmergency { unit_id }).await?)
+}
+
+#[tauri::command]
+pub async fn start_roll_call(state: State<'_, WebSocketState>, reason: String) -> Result<(), CommandError> {
+    expect_ack(state.request(ProtocolMessage::StartRollCall { reason }).await?)
+}
+
+#[tauri::command]
+pub async fn mark_unit_accounted(
+    state: State<'_, WebSocketState>,
+    outbox: State<'_, Outbox>,
+    roll_call_id: RollCallId,
+    unit_id: UnitId,
+) -> Result<Delivery<()>, CommandError> {
+    let message = ProtocolMessage::MarkUnitAccounted { roll_call_id, unit_id };
+    outbox.deliver(&state, message).await?.try_map(expect_ack)
+}
+
+#[tauri::command]
+pub async fn realert_roll_call(
+    state: State<'_, WebSocketState>,
+    roll_call_id: RollCallId,
+) -> Result<(), CommandError> {
+    expect_ack(state.request(ProtocolMessage::RealertRollCall { roll_call_id }).await?)
+}
+
+#[tauri::command]
+pub async fn complete_roll_call(
+    state: State<'_, WebSocketState>,
+    roll_call_id: RollCallId,
+) -> Result<(), CommandError> {
+    expect_ack(state.request(ProtocolMessage::CompleteRollCall { roll_call_id }).await?)
+}
+
+#[tauri::command]
+pub async fn get_roll_calls(state: State<'_, WebSocketState>) -> Result<Vec<RollCall>, CommandError> {
+    match state.request(ProtocolMessage::GetRollCalls).await? {
+        ProtocolMessage::RollCalls { roll_calls } => Ok(roll_calls),
+        other => Err(CommandError::unexpected(&other)),
+    }
+}
+
+#[tauri::command]
+pub async fn get_locations(state: State<'_, WebSocketState>) -> Result<Vec<Location>, CommandError> {
+    match state.request(ProtocolMessage::GetLocations).await? {
+        ProtocolMessage::Locations { locations } => Ok(locations),
+        other => Err(CommandError::unexpected(&other)),
+    }
+}
+
+#[tauri::command]
+pub async fn resolve_location(state: State<'_, WebSocketState>, query: String) -> Result<Vec<Location>, CommandError> {
+    match state.request(ProtocolMessage::ResolveLocation { query }).await? {
+        ProtocolMessage::LocationMatches { matches, .. } => Ok(matches),
+        other => Err(CommandError::unexpected(&other)),
+    }
+}
+
+#[tauri::command]
+pub async fn create_location(
+    state: State<'_, WebSocketState>,
+    location: NewLocation,
+) -> Result<Location, CommandError> {
+    match state.request(ProtocolMessage::CreateLocation { location }).await? {
+        ProtocolMessage::Location { location } => Ok(location),
+        other => Err(CommandError::unexpected(&other)),
+    }
+}
+
+#[tauri::command]
+pub async fn set_location_position(
+    state: State<'_, WebSocketState>,
+    location_id: LocationId,
+    position: Position,
+) -> Result<Location, CommandError> {
+    match state.request(ProtocolMessage::SetLocationPosition { location_id, position }).await? {
+        ProtocolMessage::Location { location } => Ok(location),
+        other => Err(CommandError::unexpected(&other)),
+    }
+}
+
+/// Returns how many registered locations the layer positioned.
+#[tauri::command]
+pub async fn import_map_layer(
+    state: State<'_, WebSocketState>,
+    name: String,
+    geojson: serde_json::Value,
+) -> Result<usize, CommandError> {
+    match state.request(ProtocolMessage::ImportMapLayer { name, geojson }).await? {
+        ProtocolMessage::MapLayerImported { located, .. } => Ok(located),
+        other => Err(CommandError::unexpected(&other)),
+    }
+}
+
+#[tauri::command]
+pub async fn get_map_layers(state: State<'_, WebSocketState>) -> Result<Vec<MapLayer>, CommandError> {
+    match state.request(ProtocolMessage::GetMapLayers).await? {
+        ProtocolMessage::MapLayers { layers } => Ok(layers),
+        other => Err(CommandError::unexpected(&other)),
+    }
+}
+
+#[tauri::command]
+pub async fn create_location_link(
+    state: State<'_, WebSocketState>,
+    link: NewLocationLink,
+) -> Result<LocationLink, CommandError> {
+    match state.request(ProtocolMessage::CreateLocationLink { link }).await? {
+        ProtocolMessage::LocationLinks { mut links } if links.len() == 1 => Ok(links.remove(0)),
+        other => Err(CommandError::unexpected(&other)),
+    }
+}
+
+#[tauri::command]
+pub async fn get_location_links(state: State<'_, WebSocketState>) -> Result<Vec<LocationLink>, CommandError> {
+    match state.request(ProtocolMessage::GetLocationLinks).await? {
+        ProtocolMessage::LocationLinks { links } => Ok(links),
+        other => Err(CommandError::unexpected(&other)),
+    }
+}
+
+/// Returns `None` when the locations are not connected.
+#[tauri::command]
+pub async fn get_route(
+    state: State<'_, WebSocketState>,
+    from_location_id: LocationId,
+    to_location_id: LocationId,
+    accessible_only: bool,
+) -> Result<Option<Route>, CommandError> {
+    let request = ProtocolMessage::GetRoute { from_location_id, to_location_id, accessible_only };
+    match state.request(request).await? {
+        ProtocolMessage::Route { route } => Ok(route),
+        other => Err(CommandError::unexpected(&other)),
+    }
+}
@@ -0,0 +1,118 @@
+//! Connection state reported to the frontend, and the reconnect backoff.
+
+use std::time::Duration;
+
+use rand::Rng;
+use serde::Serialize;
+
+/// Delay before the first retry; doubled on every failed attempt.
+const BASE_DELAY: Duration = Duration::from_secs(1);
+/// Longest the client ever waits between attempts.
+const MAX_DELAY: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ConnectionState {
+    /// Fetching a WARP token for the server
+    Authenticating,
+    /// Opening the WebSocket and waiting for the server's Welcome
+    Connecting,
+    Connected,
+    /// Still connected, but the server has stopped answering heartbeats
+    Degraded,
+    /// Not connected; `retry_at` says when the next attempt is due, if ever
+    Offline,
+}
+
+/// Payload of the `ws-status` event.
+#[derive(Debug, Clone, Serialize)]
+pub struct ConnectionStatus {
+    pub state: ConnectionState,
+    /// Why the client is in this state, for display
+    pub reason: Option<String>,
+    /// Unix time in milliseconds of the next connection attempt
+    pub retry_at: Option<i64>,
+}
+
+impl ConnectionStatus {
+    pub fn new(state: ConnectionState) -> Self {
+        Self {
+            state,
+            reason: None,
+            retry_at: None,
+        }
+    }
+
+    pub fn with_reason(mut self, reason: impl Into<String>) -> Self {
+        self.reason = Some(reason.into());
+        self
+    }
+
+    pub fn retrying_in(mut self, delay: Duration) -> Self {
+        let delay = chrono::Duration::from_std(delay).unwrap_or(chrono::Duration::MAX);
+        self.retry_at = Some((chrono::Utc::now() + delay).timestamp_millis());
+        self
+    }
+}
+
+/// Exponential backoff with jitter, so a server restart isn't met by every
+/// client reconnecting in the same instant.
+#[derive(Debug, Default)]
+pub struct Backoff {
+    attempt: u32,
+}
+
+impl Backoff {
+    /// The delay before the next attempt: somewhere between half and all of
+    /// the current exponential step.
+    pub fn next_delay(&mut self) -> Duration {
+        let step = BASE_DELAY
+            .saturating_mul(1 << self.attempt.min(16))
+            .min(MAX_DELAY);
+        self.attempt = self.attempt.saturating_add(1);
+
+        let half = step / 2;
+        let jitter = rand::thread_rng().gen_range(Duration::ZERO..=half);
+        half + jitter
+    }
+
+    /// Starts over after a connection that stayed up.
+    pub fn reset(&mut self) {
+        self.attempt = 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn delays_double_with_jitter() {
+        let mut backoff = Backoff::default();
+        for step in [1, 2, 4, 8, 16, 32] {
+            let step = Duration::from_secs(step);
+            let delay = backoff.next_delay();
+            assert!(delay >= step / 2 && delay <= step, "{delay:?} outside {:?}..={step:?}", step / 2);
+        }
+    }
+
+    #[test]
+    fn delays_stop_growing_at_the_cap() {
+        let mut backoff = Backoff::default();
+        for _ in 0..100 {
+            let delay = backoff.next_delay();
+            assert!(delay <= MAX_DELAY, "{delay:?}");
+        }
+        assert!(backoff.next_delay() >= MAX_DELAY / 2);
+    }
+
+    #[test]
+    fn reset_starts_over() {
+        let mut backoff = Backoff::default();
+        for _ in 0..10 {
+            backoff.next_delay();
+        }
+        backoff.reset();
+        assert!(backoff.next_delay() <= BASE_DELAY);
+    }
+}
@@ -0,0 +1,241 @@
+//! Connection health for the dispatcher and for support: round-trip time,
+//! reconnects, what is waiting to go out and the last error, plus the recent
+//! log.
+//!
+//! The figures go to every window as a `diagnostics` event on each heartbeat
+//! and whenever the connection changes. [`save_diagnostics_bundle`] writes them
+//! to a file together with the recent log, for the dispatcher to hand to
+//! support. The bundle counts calls and units but leaves out their details,
+//! which may identify patients.
+
+use std::collections::VecDeque;
+use std::sync::Mutex;
+use std::time::Instant;
+
+use serde::Serialize;
+use tauri::{AppHandle, Emitter, Manager, State};
+
+use crate::connection::ConnectionStatus;
+use crate::outbox::{Outbox, OutboxStatus};
+use crate::profiles::{Profiles, ServerProfile};
+use crate::WebSocketState;
+
+/// Log lines kept for the diagnostics bundle.
+const LOG_LINES: usize = 500;
+/// Pings the average round-trip time is taken over.
+const RTT_SAMPLES: usize = 10;
+
+static RECENT_LOG: Mutex<VecDeque<String>> = Mutex::new(VecDeque::new());
+
+/// Keeps a line for the diagnostics bundle.
+macro_rules! log {
+    ($($arg:tt)*) => {
+        $crate::diagnostics::record(format!($($arg)*))
+    };
+}
+
+pub fn record(line: String) {
+    let mut log = RECENT_LOG.lock().unwrap();
+    if log.len() == LOG_LINES {
+        log.pop_front();
+    }
+    log.push_back(format!("{} {}", chrono::Local::now().format("%Y-%m-%d %H:%M:%S%.3f"), line));
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct LastError {
+    pub message: String,
+    /// Unix time in milliseconds
+    pub at: i64,
+}
+
+/// Payload of the `diagnostics` event.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct Report {
+    /// As last sent in `ws-status`
+    pub status: Option<ConnectionStatus>,
+    /// Unix time in milliseconds the current connection was made
+    pub connected_since: Option<i64>,
+    /// Round-trip time of the last heartbeat
+    pub rtt_ms: Option<u64>,
+    pub rtt_avg_ms: Option<u64>,
+    pub connection_attempts: u32,
+    /// Connections made after the first
+    pub reconnects: u32,
+    /// Messages waiting to be written to the socket
+    pub queued: usize,
+    /// Requests waiting for the server's reply
+    pub pending_requests: usize,
+    pub messages_sent: u64,
+    pub messages_received: u64,
+    pub last_error: Option<LastError>,
+}
+
+#[derive(Default)]
+struct Inner {
+    report: Report,
+    connections: u32,
+    ping_sent: Option<Instant>,
+    rtt_samples: VecDeque<u64>,
+}
+
+#[derive(Default)]
+pub struct Diagnostics {
+    inner: Mutex<Inner>,
+}
+
+impl Diagnostics {
+    pub fn report(&self) -> Report {
+        self.inner.lock().unwrap().report.clone()
+    }
+
+    pub fn emit(&self, app: &AppHandle) {
+        app.emit("diagnostics", self.report()).unwrap_or_default();
+    }
+
+    pub fn set_status(&self, status: ConnectionStatus) {
+        self.inner.lock().unwrap().report.status = Some(status);
+    }
+
+    pub fn attempt(&self) {
+        self.inner.lock().unwrap().report.connection_attempts += 1;
+    }
+
+    pub fn connected(&self) {
+        let mut inner = self.inner.lock().unwrap();
+        inner.connections += 1;
+        inner.report.reconnects = inner.connections - 1;
+        inner.report.connected_since = Some(chrono::Utc::now().timestamp_millis());
+    }
+
+    pub fn disconnected(&self) {
+        let mut inner = self.inner.lock().unwrap();
+        inner.report.connected_since = None;
+        inner.report.rtt_ms = None;
+        inner.ping_sent = None;
+    }
+
+    pub fn error(&self, message: &str) {
+        self.inner.lock().unwrap().report.last_error = Some(LastError {
+            message: message.to_string(),
+            at: chrono::Utc::now().timestamp_millis(),
+        });
+    }
+
+    pub fn ping_sent(&self) {
+        self.inner.lock().unwrap().ping_sent = Some(Instant::now());
+    }
+
+    pub fn pong_received(&self) {
+        let mut inner = self.inner.lock().unwrap();
+        let Some(sent) = inner.ping_sent.take() else {
+            return;
+        };
+        let rtt = sent.elapsed().as_millis() as u64;
+        if inner.rtt_samples.len() == RTT_SAMPLES {
+            inner.rtt_samples.pop_front();
+        }
+        inner.rtt_samples.push_back(rtt);
+        inner.report.rtt_ms = Some(rtt);
+        inner.report.rtt_avg_ms = Some(inner.rtt_samples.iter().sum::<u64>() / inner.rtt_samples.len() as u64);
+    }
+
+    pub fn set_backlog(&self, queued: usize, pending_requests: usize) {
+        let mut inner = self.inner.lock().unwrap();
+        inner.report.queued = queued;
+        inner.report.pending_requests = pending_requests;
+    }
+
+    pub fn sent(&self) {
+        self.inner.lock().unwrap().report.messages_sent += 1;
+    }
+
+    pub fn received(&self) {
+        self.inner.lock().unwrap().report.messages_received += 1;
+    }
+}
+
+#[derive(Serialize)]
+struct LocalStateSummary {
+    event: Option<String>,
+    calls: usize,
+    units: usize,
+    stale_since: Option<i64>,
+}
+
+#[derive(Serialize)]
+struct OutboxSummary {
+    id: u64,
+    created_at: i64,
+    operation: String,
+    status: OutboxStatus,
+    error: Option<String>,
+}
+
+#[derive(Serialize)]
+struct Bundle {
+    created_at: String,
+    app_version: &'static str,
+    os: &'static str,
+    arch: &'static str,
+    server: ServerProfile,
+    diagnostics: Report,
+    local_state: LocalStateSummary,
+    outbox: Vec<OutboxSummary>,
+    log: Vec<String>,
+}
+
+#[tauri::command]
+pub fn get_diagnostics(state: State<'_, WebSocketState>) -> Report {
+    state.diagnostics.report()
+}
+
+/// Writes a diagnostics bundle to the downloads folder and returns its path.
+#[tauri::command]
+pub fn save_diagnostics_bundle(
+    app: AppHandle,
+    state: State<'_, WebSocketState>,
+    profiles: State<'_, Profiles>,
+    outbox: State<'_, Outbox>,
+) -> Result<String, String> {
+    let now = chrono::Local::now();
+    let snapshot = state.local.snapshot();
+    let bundle = Bundle {
+        created_at: now.to_rfc3339(),
+        app_version: env!("CARGO_PKG_VERSION"),
+        os: std::env::consts::OS,
+        arch: std::env::consts::ARCH,
+        server: profiles.watch().borrow().clone(),
+        diagnostics: state.diagnostics.report(),
+        local_state: LocalStateSummary {
+            event: snapshot.event.map(|event| event.name),
+            calls: snapshot.calls.len(),
+            units: snapshot.units.len(),
+            stale_since: snapshot.stale_since,
+        },
+        outbox: outbox
+            .items()
+            .into_iter()
+            .map(|item| OutboxSummary {
+                id: item.id,
+                created_at: item.created_at,
+                operation: item.message.message_type(),
+                status: item.status,
+                error: item.error,
+            })
+            .collect(),
+        log: RECENT_LOG.lock().unwrap().iter().cloned().collect(),
+    };
+
+    let dir = app
+        .path()
+        .download_dir()
+        .or_else(|_| app.path().app_log_dir())
+        .map_err(|e| e.to_string())?;
+    std::fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+    let path = dir.join(format!("rover-diagnostics-{}.json", now.format("%Y%m%d-%H%M%S")));
+    let json = serde_json::to_vec_pretty(&bundle).map_err(|e| e.to_string())?;
+    std::fs::write(&path, json).map_err(|e| e.to_string())?;
+    log!("Saved diagnostics bundle to {}", path.display());
+    Ok(path.display().to_string())
+}
@@ -0,0 +1,449 @@
+//! The client's copy of the active event, its open calls, its units and who
+//! else is dispatching on it.
+//!
+//! Every message from the server passes through here before anything else sees
+//! it. Snapshots (`ActiveCalls`, `Units`) replace what we hold and deltas
+//! (`CallUpdated`, `UnitUpdated`, ...) patch it; each change goes out to every
+//! window as a `state-change` event, so windows never have to piece the state
+//! together from raw messages themselves.
+//!
+//! While the client is offline the state is stale: still shown, read-only and
+//! marked with when it was last in step, until the server's fresh snapshots
+//! have replaced both the calls and the units.
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use shared_types::event::Event;
+use shared_types::id::{EventId, IncidentId, UnitId};
+use shared_types::incident::{IncidentCall, Unit};
+use shared_types::presence::OnlineDispatcher;
+use shared_types::ProtocolMessage;
+use tokio::sync::Notify;
+
+/// Everything the client holds, for windows that open after the fact.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Snapshot {
+    pub event: Option<Event>,
+    /// Open calls, highest priority and longest waiting first
+    pub calls: Vec<IncidentCall>,
+    pub units: Vec<Unit>,
+    /// Dispatchers with a console on the event
+    #[serde(default)]
+    pub presence: Vec<OnlineDispatcher>,
+    /// Unix time in milliseconds the state was last in step with the server,
+    /// while it is stale
+    #[serde(default)]
+    pub stale_since: Option<i64>,
+}
+
+/// Payload of the `state-change` event.
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", content = "payload", rename_all = "snake_case")]
+pub enum StateChange {
+    /// Calls, units and presence were cleared and will be reloaded for the new event
+    ActiveEventChanged { event: Event },
+    CallsReplaced { calls: Vec<IncidentCall> },
+    /// A call was opened or changed
+    CallUpdated { call: Box<IncidentCall> },
+    /// A call was cleared or no longer belongs to the active event
+    CallRemoved { id: IncidentId },
+    UnitsReplaced { units: Vec<Unit> },
+    UnitUpdated { unit: Unit },
+    PresenceChanged { online: Vec<OnlineDispatcher> },
+    /// The state went stale, or is in step with the server again when `since` is `None`
+    StaleChanged { since: Option<i64> },
+    /// Everything was swapped out after switching to another server
+    Replaced { snapshot: Snapshot },
+}
+
+/// Stale state waiting to be replaced by the server's snapshots.
+struct Stale {
+    since: i64,
+    calls_reloaded: bool,
+    units_reloaded: bool,
+}
+
+#[derive(Default)]
+struct Inner {
+    event: Option<Event>,
+    calls: HashMap<IncidentId, IncidentCall>,
+    units: HashMap<UnitId, Unit>,
+    presence: Vec<OnlineDispatcher>,
+    stale: Option<Stale>,
+}
+
+impl Inner {
+    /// Whether data from `event_id` belongs in the store. Until the server
+    /// tells us the active event, everything it sends is for it.
+    fn is_current(&self, event_id: EventId) -> bool {
+        self.event.as_ref().is_none_or(|event| event.id == event_id)
+    }
+
+    fn update_call(&mut self, call: &IncidentCall) -> Option<StateChange> {
+        if !self.is_current(call.event_id) {
+            return None;
+        }
+        if call.times.cleared.is_some() {
+            return self
+                .calls
+                .remove(&call.id)
+                .map(|call| StateChange::CallRemoved { id: call.id });
+        }
+        self.calls.insert(call.id, call.clone());
+        Some(StateChange::CallUpdated { call: Box::new(call.clone()) })
+    }
+
+    fn update_unit(&mut self, unit: &Unit) -> Option<StateChange> {
+        if !self.is_current(unit.event_id) {
+            return None;
+        }
+        self.units.insert(unit.id, unit.clone());
+        Some(StateChange::UnitUpdated { unit: unit.clone() })
+    }
+
+    fn sorted_calls(&self) -> Vec<IncidentCall> {
+        let mut calls: Vec<_> = self.calls.values().cloned().collect();
+        calls.sort_by_key(|call| (call.priority, call.times.received));
+        calls
+    }
+
+    fn sorted_units(&self) -> Vec<Unit> {
+        let mut units: Vec<_> = self.units.values().cloned().collect();
+        units.sort_by(|a, b| a.name.cmp(&b.name));
+        units
+    }
+}
+
+#[derive(Default)]
+pub struct LocalState {
+    inner: Mutex<Inner>,
+    changed: Notify,
+}
+
+impl LocalState {
+    pub fn snapshot(&self) -> Snapshot {
+        let inner = self.inner.lock().unwrap();
+        Snapshot {
+            event: inner.event.clone(),
+            calls: inner.sorted_calls(),
+            units: inner.sorted_units(),
+            presence: inner.presence.clone(),
+            stale_since: inner.stale.as_ref().map(|stale| stale.since),
+        }
+    }
+
+    /// Whether data from `event_id` belongs in the store.
+    pub fn is_current(&self, event_id: EventId) -> bool {
+        self.inner.lock().unwrap().is_current(event_id)
+    }
+
+    pub fn call(&self, id: IncidentId) -> Option<IncidentCall> {
+        self.inner.lock().unwrap().calls.get(&id).cloned()
+    }
+
+    /// The state, unless it is stale and so not worth keeping.
+    pub fn live_snapshot(&self) -> Option<Snapshot> {
+        let snapshot = self.snapshot();
+        snapshot.stale_since.is_none().then_some(snapshot)
+    }
+
+    /// Waits until the state has changed since the last call.
+    pub async fn changed(&self) {
+        self.changed.notified().await
+    }
+
+    /// Starts from a snapshot kept from an earlier run, stale since it was taken.
+    pub fn restore(&self, snapshot: Snapshot, since: i64) {
+        let mut inner = self.inner.lock().unwrap();
+        inner.event = snapshot.event;
+        inner.calls = snapshot.calls.into_iter().map(|call| (call.id, call)).collect();
+        inner.units = snapshot.units.into_iter().map(|unit| (unit.id, unit)).collect();
+        // Who was online then says nothing about who is now
+        inner.presence = Vec::new();
+        inner.stale = Some(Stale { since, calls_reloaded: false, units_reloaded: false });
+    }
+
+    /// Swaps in another server's state: what was cached from it, stale since it
+    /// was saved, or nothing at all.
+    pub fn replace(&self, cached: Option<(Snapshot, i64)>) -> StateChange {
+        match cached {
+            Some((snapshot, since)) => self.restore(snapshot, since),
+            None => *self.inner.lock().unwrap() = Inner::default(),
+        }
+        StateChange::Replaced { snapshot: self.snapshot() }
+    }
+
+    /// Marks the state stale once the connection has dropped. Does nothing if
+    /// there is nothing to show or it is already stale.
+    pub fn mark_stale(&self) -> Option<StateChange> {
+        let mut inner = self.inner.lock().unwrap();
+        let empty = inner.event.is_none() && inner.calls.is_empty() && inner.units.is_empty();
+        if inner.stale.is_some() || empty {
+            return None;
+        }
+        let since = chrono::Utc::now().timestamp_millis();
+        inner.stale = Some(Stale { since, calls_reloaded: false, units_reloaded: false });
+        Some(StateChange::StaleChanged { since: Some(since) })
+    }
+
+    /// Clears the stale mark once the server has sent both calls and units.
+    pub fn settle(&self) -> Option<StateChange> {
+        let mut inner = self.inner.lock().unwrap();
+        match &inner.stale {
+            Some(stale) if stale.calls_reloaded && stale.units_reloaded => {
+                inner.stale = None;
+                self.changed.notify_one();
+                Some(StateChange::StaleChanged { since: None })
+            }
+            _ => None,
+        }
+    }
+
+    /// Applies a message from the server, returning what changed.
+    pub fn apply(&self, message: &ProtocolMessage) -> Option<StateChange> {
+        let change = self.apply_inner(message);
+        if change.is_some() {
+            self.changed.notify_one();
+        }
+        change
+    }
+
+    fn apply_inner(&self, message: &ProtocolMessage) -> Option<StateChange> {
+        let mut inner = self.inner.lock().unwrap();
+        match message {
+            ProtocolMessage::ActiveEvent { event } => {
+                if inner.event.as_ref().is_some_and(|current| current.id == event.id) {
+                    inner.event = Some(event.clone());
+                    return None;
+                }
+                inner.calls.clear();
+                inner.units.clear();
+                inner.presence.clear();
+                inner.event = Some(event.clone());
+                if let Some(stale) = &mut inner.stale {
+                    stale.calls_reloaded = false;
+                    stale.units_reloaded = false;
+                }
+                Some(StateChange::ActiveEventChanged { event: event.clone() })
+            }
+            ProtocolMessage::ActiveCalls { calls } => {
+                inner.calls = calls.iter().map(|call| (call.id, call.clone())).collect();
+                if let Some(stale) = &mut inner.stale {
+                    stale.calls_reloaded = true;
+                }
+                Some(StateChange::CallsReplaced { calls: inner.sorted_calls() })
+            }
+            ProtocolMessage::Units { units } => {
+                inner.units = units.iter().map(|unit| (unit.id, unit.clone())).collect();
+                if let Some(stale) = &mut inner.stale {
+                    stale.units_reloaded = true;
+                }
+                Some(StateChange::UnitsReplaced { units: inner.sorted_units() })
+            }
+            ProtocolMessage::Call { call } | ProtocolMessage::CallUpdated { call } => inner.update_call(call),
+            ProtocolMessage::CallEscalated { id, waiting_secs, .. } => inner.calls.get_mut(id).map(|call| {
+                let waited = Duration::from_secs((*waiting_secs).max(0) as u64);
+                call.times.escalated = Some(call.times.received + waited);
+                StateChange::CallUpdated { call: Box::new(call.clone()) }
+            }),
+            ProtocolMessage::UnitUpdated { unit } | ProtocolMessage::EmergencyAlert { unit, .. } => {
+                inner.update_unit(unit)
+            }
+            ProtocolMessage::PresenceChanged { event_id, online } => {
+                if !inner.is_current(*event_id) {
+                    return None;
+                }
+                inner.presence = online.clone();
+                Some(StateChange::PresenceChanged { online: online.clone() })
+            }
+            ProtocolMessage::EmergencyCleared { unit_id, .. } => inner.units.get_mut(unit_id).map(|unit| {
+                unit.emergency_since = None;
+                StateChange::UnitUpdated { unit: unit.clone() }
+            }),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use serde_json::json;
+    use shared_types::incident::Priority;
+
+    pub(crate) const EVENT: &str = "a3f1c2d4-1111-4b6a-8e9f-0123456789ab";
+    pub(crate) const OTHER_EVENT: &str = "b4e2d3c5-2222-4c7b-9f0a-123456789abc";
+
+    fn id(n: u8) -> String {
+        format!("00000000-0000-4000-8000-0000000000{n:02x}")
+    }
+
+    pub(crate) fn event(id: &str) -> Event {
+        serde_json::from_value(json!({
+            "id": id,
+            "name": "FurCon 2024",
+            "starts_on": "2024-11-01",
+            "ends_on": "2024-11-03",
+            "time_zone": "America/Chicago",
+            "settings": {},
+        }))
+        .unwrap()
+    }
+
+    pub(crate) fn unit(n: u8, event_id: &str) -> Unit {
+        serde_json::from_value(json!({
+            "id": id(n),
+            "event_id": event_id,
+            "name": format!("Medic {n}"),
+            "unit_type": "first_aid",
+            "status": "available",
+            "status_since": "2024-11-03T06:00:00Z",
+            "last_check_in": null,
+            "last_known_location": null,
+            "location_id": null,
+            "last_position": null,
+            "emergency_since": null,
+        }))
+        .unwrap()
+    }
+
+    /// A pending call received `n` minutes after 06:00.
+    pub(crate) fn call(n: u8, event_id: &str, priority: &str) -> IncidentCall {
+        serde_json::from_value(json!({
+            "id": id(n),
+            "incident_number": format!("24-{n:06}"),
+            "event_id": event_id,
+            "date_of_service": "2024-11-03T06:00:00Z",
+            "name": "Jane Doe",
+            "location": { "location_id": null, "name": "Ballroom B", "detail": null },
+            "dob": null,
+            "badge_number": null,
+            "phone_number": "555-0100",
+            "caller_name": "Front desk",
+            "incident_type": "medical",
+            "priority": priority,
+            "call_nature": {
+                "code": "CHEST",
+                "description": "Chest pain",
+                "default_priority": "high",
+                "default_incident_type": "medical",
+                "recommended_unit_types": ["first_aid"],
+            },
+            "notes": [],
+            "disposition": "pending",
+            "units_assigned": [],
+            "times": { "received": format!("2024-11-03T06:{n:02}:00Z") },
+        }))
+        .unwrap()
+    }
+
+    fn call_ids(state: &LocalState) -> Vec<IncidentId> {
+        state.snapshot().calls.iter().map(|call| call.id).collect()
+    }
+
+    #[test]
+    fn snapshots_replace_and_deltas_patch() {
+        let state = LocalState::default();
+        let calls = vec![call(1, EVENT, "low"), call(2, EVENT, "high")];
+        state.apply(&ProtocolMessage::ActiveCalls { calls });
+        assert_eq!(call_ids(&state), vec![call(2, EVENT, "high").id, call(1, EVENT, "low").id]);
+
+        let change = state.apply(&ProtocolMessage::CallUpdated { call: call(3, EVENT, "high") });
+        assert!(matches!(change, Some(StateChange::CallUpdated { .. })));
+        assert_eq!(call_ids(&state).len(), 3);
+
+        let mut cleared = call(1, EVENT, "low");
+        cleared.times.cleared = Some(cleared.times.received);
+        let change = state.apply(&ProtocolMessage::CallUpdated { call: cleared.clone() });
+        assert!(matches!(change, Some(StateChange::CallRemoved { id }) if id == cleared.id));
+        assert!(state.call(cleared.id).is_none());
+    }
+
+    #[test]
+    fn data_for_other_events_is_ignored() {
+        let state = LocalState::default();
+        state.apply(&ProtocolMessage::ActiveEvent { event: event(EVENT) });
+
+        assert!(state.apply(&ProtocolMessage::CallUpdated { call: call(1, OTHER_EVENT, "high") }).is_none());
+        assert!(state.apply(&ProtocolMessage::UnitUpdated { unit: unit(1, OTHER_EVENT) }).is_none());
+        let presence = ProtocolMessage::PresenceChanged { event_id: event(OTHER_EVENT).id, online: Vec::new() };
+        assert!(state.apply(&presence).is_none());
+        assert!(state.snapshot().calls.is_empty() && state.snapshot().units.is_empty());
+    }
+
+    #[test]
+    fn a_new_event_starts_empty() {
+        let state = LocalState::default();
+        state.apply(&ProtocolMessage::ActiveEvent { event: event(EVENT) });
+        state.apply(&ProtocolMessage::CallUpdated { call: call(1, EVENT, "high") });
+        state.apply(&ProtocolMessage::UnitUpdated { unit: unit(1, EVENT) });
+
+        // The same event again only refreshes its details
+        assert!(state.apply(&ProtocolMessage::ActiveEvent { event: event(EVENT) }).is_none());
+        assert_eq!(state.snapshot().calls.len(), 1);
+
+        let change = state.apply(&ProtocolMessage::ActiveEvent { event: event(OTHER_EVENT) });
+        assert!(matches!(change, Some(StateChange::ActiveEventChanged { .. })));
+        let snapshot = state.snapshot();
+        assert!(snapshot.calls.is_empty() && snapshot.units.is_empty() && snapshot.presence.is_empty());
+    }
+
+    #[test]
+    fn escalation_is_recorded_without_touching_priority() {
+        let state = LocalState::default();
+        let pending = call(1, EVENT, "low");
+        state.apply(&ProtocolMessage::CallUpdated { call: pending.clone() });
+
+        let escalated = ProtocolMessage::CallEscalated { id: pending.id, priority: Priority::Low, waiting_secs: 600 };
+        assert!(state.apply(&escalated).is_some());
+        let call = state.call(pending.id).unwrap();
+        assert_eq!(call.priority, Priority::Low);
+        assert_eq!(call.times.escalated, Some(pending.times.received + Duration::from_secs(600)));
+    }
+
+    #[test]
+    fn stale_until_calls_and_units_are_reloaded() {
+        let state = LocalState::default();
+        // Nothing to show, so nothing to mark
+        assert!(state.mark_stale().is_none());
+
+        // No event is known until the server says, but the calls still go stale
+        state.apply(&ProtocolMessage::CallUpdated { call: call(1, EVENT, "high") });
+        assert!(matches!(state.mark_stale(), Some(StateChange::StaleChanged { since: Some(_) })));
+        assert!(state.mark_stale().is_none());
+        assert!(state.live_snapshot().is_none());
+
+        state.apply(&ProtocolMessage::ActiveCalls { calls: vec![call(1, EVENT, "high")] });
+        assert!(state.settle().is_none());
+        state.apply(&ProtocolMessage::Units { units: vec![unit(1, EVENT)] });
+        assert!(matches!(state.settle(), Some(StateChange::StaleChanged { since: None })));
+        assert!(state.live_snapshot().is_some());
+    }
+
+    #[test]
+    fn replace_swaps_in_another_servers_state() {
+        let state = LocalState::default();
+        state.apply(&ProtocolMessage::ActiveCalls { calls: vec![call(1, EVENT, "high")] });
+
+        let cached = Snapshot {
+            event: Some(event(OTHER_EVENT)),
+            calls: vec![call(2, OTHER_EVENT, "low")],
+            units: vec![unit(2, OTHER_EVENT)],
+            presence: Vec::new(),
+            stale_since: None,
+        };
+        let StateChange::Replaced { snapshot } = state.replace(Some((cached, 42))) else {
+            panic!("expected a replaced state");
+        };
+        assert_eq!(snapshot.stale_since, Some(42));
+        assert_eq!(call_ids(&state), vec![call(2, OTHER_EVENT, "low").id]);
+
+        let StateChange::Replaced { snapshot } = state.replace(None) else {
+            panic!("expected a replaced state");
+        };
+        assert!(snapshot.event.is_none() && snapshot.calls.is_empty() && snapshot.stale_since.is_none());
+    }
+}
@@ -1,12 +1,36 @@
-use reqwest::{Method, Request};
 // src-tauri/src/main.rs
-use tauri::{http::{self, request, Uri}, Emitter, Url};
+use tauri::Emitter;
 use tokio::net::TcpStream;
-use tokio_tungstenite::{connect_async, tungstenite::Message, WebSocketStream, MaybeTlsStream};
+use tokio_tungstenite::{tungstenite::Message, WebSocketStream, MaybeTlsStream};
 use futures_util::{SinkExt, StreamExt};
 use std::sync::Arc;
-use tokio::sync::{mpsc, Mutex};
-use reqwest::header::{HeaderMap, HeaderValue};
+use std::time::{Duration, Instant};
+use tokio::sync::{mpsc, watch, Mutex, Notify};
+use tauri::Manager;
+use shared_types::encoding::{Encoding, Frame, DEFLATE, MSGPACK};
+use shared_types::{Envelope, ProtocolMessage, PROTOCOL_VERSION};
+
+// First, so every other module can use its `log!` macro
+#[macro_use]
+mod diagnostics;
+
+mod alerts;
+mod auth;
+mod cache;
+mod commands;
+mod connection;
+mod local_state;
+mod outbox;
+mod profiles;
+mod requests;
+mod windows;
+
+use auth::{AuthStatus, Credentials, HandshakeError};
+use connection::{Backoff, ConnectionState, ConnectionStatus};
+use diagnostics::Diagnostics;
+use local_state::{LocalState, StateChange};
+use profiles::{Profiles, ServerProfile};
+use requests::{CommandError, Requests};
 
 
 
@@ -15,191 +39,335 @@ type WsStream = WebSocketStream<MaybeTlsStream<TcpStream>>;
 // Store WebSocket connection in app state
 #[derive(Default)]
 struct WebSocketState {
-    tx: Arc<Mutex<Option<mpsc::Sender<Message>>>>,
+    tx: Arc<Mutex<Option<mpsc::Sender<Envelope>>>>,
+    /// Wakes the connection loop to reconnect without waiting out the backoff
+    reconnect: Arc<Notify>,
+    /// Commands waiting for the server to reply
+    requests: Arc<Requests>,
+    /// Calls and units of the active event, kept in step with the server
+    local: Arc<LocalState>,
+    diagnostics: Arc<Diagnostics>,
 }
 
-// Command to send ping
+impl WebSocketState {
+    /// Sends a request and waits for the server's reply to it. An `Error` reply
+    /// becomes `CommandError::Server`.
+    async fn request(&self, message: ProtocolMessage) -> Result<ProtocolMessage, CommandError> {
+        let tx = self.tx.lock().await.clone().ok_or(CommandError::NotConnected)?;
+        let (request_id, reply) = self.requests.register();
+        let envelope = Envelope { request_id: Some(request_id), message };
+        if tx.send(envelope).await.is_err() {
+            self.requests.cancel(request_id);
+            return Err(CommandError::NotConnected);
+        }
+
+        match tokio::time::timeout(REQUEST_TIMEOUT, reply).await {
+            Ok(Ok(ProtocolMessage::Error { message })) => Err(CommandError::Server(message)),
+            Ok(Ok(reply)) => Ok(reply),
+            Ok(Err(_)) => Err(CommandError::Disconnected),
+            Err(_) => {
+                self.requests.cancel(request_id);
+                Err(CommandError::Timeout)
+            }
+        }
+    }
+}
+
+/// How often the client pings the server while connected.
+const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(10);
+/// Silence after which the connection is reported as degraded.
+const DEGRADED_AFTER: Duration = Duration::from_secs(20);
+/// Silence after which the connection is given up and re-established.
+const DEAD_AFTER: Duration = Duration::from_secs(45);
+/// A connection that stays up this long resets the backoff.
+const STABLE_AFTER: Duration = Duration::from_secs(30);
+/// Minimum wait after the server refuses our account.
+const FORBIDDEN_RETRY: Duration = Duration::from_secs(30);
+/// How long a command waits for the server's reply.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(15);
+
+// Command to drop any pending backoff (or a stuck connection) and reconnect.
+// Only a loop already waiting is woken; `notify_one` would store a permit
+// that cuts short whatever connection comes next.
 #[tauri::command]
-async fn send_ping(state: tauri::State<'_, WebSocketState>) -> Result<(), String> {
-    println!("Attempting to send ping message...");
-    
-    let tx_lock = state.tx.lock().await;
-    if let Some(tx) = tx_lock.as_ref() {
-        tx.send(Message::Text("PING".to_string()))
-            .await
-            .map_err(|e| {
-                println!("Error sending ping: {}", e);
-                e.to_string()
-            })?;
-        println!("Ping sent successfully");
-        Ok(())
-    } else {
-        println!("No sender available - websocket not connected");
-        Err("WebSocket not connected".to_string())
+fn reconnect_now(state: tauri::State<'_, WebSocketState>) {
+    state.reconnect.notify_waiters();
+}
+
+/// Why a connection ended, so the reconnect loop knows whether to try again.
+enum ConnectionEnd {
+    Disconnected(String),
+    /// The server cannot speak our protocol version; retrying will not help
+    UpgradeRequired(String),
+}
+
+fn emit_status(app_handle: &tauri::AppHandle, status: ConnectionStatus) {
+    let diagnostics = &app_handle.state::<WebSocketState>().diagnostics;
+    diagnostics.set_status(status.clone());
+    diagnostics.emit(app_handle);
+    app_handle.emit("ws-status", status).unwrap_or_default();
+}
+
+fn hello() -> Message {
+    let hello = ProtocolMessage::Hello {
+        min_version: PROTOCOL_VERSION,
+        max_version: PROTOCOL_VERSION,
+        client: concat!("rover/", env!("CARGO_PKG_VERSION")).to_string(),
+        // Binary frames are much smaller on congested convention Wi-Fi
+        capabilities: vec![MSGPACK.to_string(), DEFLATE.to_string()],
+    };
+    Message::Text(serde_json::to_string(&hello).expect("hello serializes"))
+}
+
+/// Asks for the active event's calls and units, which replace whatever the
+/// local state holds when they arrive.
+fn request_snapshot(outbound: &mpsc::Sender<Envelope>) {
+    for message in [ProtocolMessage::GetActiveCalls, ProtocolMessage::GetUnits] {
+        if let Err(e) = outbound.try_send(message.into()) {
+            log!("Failed to request snapshot: {}", e);
+        }
+    }
+}
+
+/// Handles one message from the server: it raises any alerts and updates the
+/// local state, then replies go to the command awaiting them and everything
+/// else to the frontend. Returns the server's explanation if it cannot speak
+/// our protocol version.
+fn handle_envelope(
+    envelope: Envelope,
+    app_handle: &tauri::AppHandle,
+    state: &WebSocketState,
+    outbound: &mpsc::Sender<Envelope>,
+    encoding: &watch::Sender<Encoding>,
+) -> Option<String> {
+    alerts::check(app_handle, &state.local, &envelope.message);
+    if let Some(change) = state.local.apply(&envelope.message) {
+        if let StateChange::ActiveEventChanged { .. } = change {
+            request_snapshot(outbound);
+        }
+        app_handle.emit("state-change", change).unwrap_or_default();
     }
+    if let Some(change) = state.local.settle() {
+        app_handle.emit("state-change", change).unwrap_or_default();
+    }
+
+    let message = state.requests.resolve(envelope)?.message;
+    let mut upgrade_required = None;
+    match &message {
+        ProtocolMessage::Welcome { version, server, capabilities } => {
+            log!("Connected to {} using protocol v{} with {:?}", server, version, capabilities);
+            encoding.send_replace(Encoding::from_capabilities(capabilities));
+            emit_status(app_handle, ConnectionStatus::new(ConnectionState::Connected));
+            app_handle.emit("ws-welcome", version).unwrap_or_default();
+            // Anything we held may have changed while we were away
+            request_snapshot(outbound);
+            outbox::start_flush(app_handle);
+        }
+        ProtocolMessage::UnsupportedVersion { message, .. } => {
+            log!("Server rejected protocol v{}: {}", PROTOCOL_VERSION, message);
+            app_handle.emit("ws-upgrade-required", message).unwrap_or_default();
+            upgrade_required = Some(message.clone());
+        }
+        _ => {}
+    }
+    // The frontend only speaks JSON, so binary messages are re-encoded for it
+    match serde_json::to_string(&message) {
+        Ok(text) => app_handle.emit("ws-message", text).unwrap_or_default(),
+        Err(e) => log!("Failed to re-encode message: {}", e),
+    }
+    upgrade_required
 }
 
 async fn handle_ws_messages(
     ws: WsStream,
-    mut rx: mpsc::Receiver<Message>,
-    app_handle: tauri::AppHandle
-) {
+    outbound: mpsc::Sender<Envelope>,
+    mut rx: mpsc::Receiver<Envelope>,
+    app_handle: tauri::AppHandle,
+    state: Arc<WebSocketState>,
+) -> ConnectionEnd {
     let (ws_sink, mut ws_stream) = ws.split();
     let ws_sink = Arc::new(Mutex::new(ws_sink));
     let ws_sink_clone = ws_sink.clone();
+    let mut upgrade_required = None;
+    // JSON text until the server's Welcome says otherwise
+    let (encoding_tx, encoding_rx) = watch::channel(Encoding::default());
+
+    // Introduce ourselves before anything else goes out
+    if let Err(e) = ws_sink.lock().await.send(hello()).await {
+        log!("Failed to send hello: {}", e);
+        return ConnectionEnd::Disconnected(format!("Failed to send hello: {}", e));
+    }
 
 
     // Task to send messages to WebSocket
+    let diagnostics = state.diagnostics.clone();
     let send_task = tokio::spawn(async move {
-        while let Some(msg) = rx.recv().await {
+        while let Some(envelope) = rx.recv().await {
+            let encoding = *encoding_rx.borrow();
+            let msg = match encoding.encode(&envelope) {
+                Ok(Frame::Text(text)) => Message::Text(text),
+                Ok(Frame::Binary(data)) => Message::Binary(data),
+                Err(e) => {
+                    log!("Failed to encode message: {}", e);
+                    continue;
+                }
+            };
             let mut sink = ws_sink_clone.lock().await;
             if sink.send(msg).await.is_err() {
                 break;
             }
+            diagnostics.sent();
         }
     });
 
-    // Task to handle incoming WebSocket messages
-    while let Some(msg) = ws_stream.next().await {
+    let mut heartbeat = tokio::time::interval(HEARTBEAT_INTERVAL);
+    heartbeat.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+    let mut last_seen = Instant::now();
+    let mut degraded = false;
+
+    // Handle incoming WebSocket messages, pinging the server when it goes quiet
+    let reason = loop {
+        let msg = tokio::select! {
+            msg = ws_stream.next() => msg,
+            _ = heartbeat.tick() => {
+                let silence = last_seen.elapsed();
+                if silence >= DEAD_AFTER {
+                    log!("No response from server for {:?}", silence);
+                    break "Server stopped responding".to_string();
+                }
+                if silence >= DEGRADED_AFTER && !degraded {
+                    degraded = true;
+                    emit_status(
+                        &app_handle,
+                        ConnectionStatus::new(ConnectionState::Degraded)
+                            .with_reason(format!("No response from server for {} seconds", silence.as_secs())),
+                    );
+                }
+                state.diagnostics.ping_sent();
+                if let Err(e) = ws_sink.lock().await.send(Message::Ping(Vec::new())).await {
+                    break format!("Failed to send heartbeat: {}", e);
+                }
+                let queued = outbound.max_capacity() - outbound.capacity();
+                state.diagnostics.set_backlog(queued, state.requests.pending_count());
+                state.diagnostics.emit(&app_handle);
+                continue;
+            }
+        };
+
+        let Some(msg) = msg else {
+            break "Connection closed".to_string();
+        };
+        last_seen = Instant::now();
+        state.diagnostics.received();
+        if degraded {
+            degraded = false;
+            emit_status(&app_handle, ConnectionStatus::new(ConnectionState::Connected));
+        }
+
         match msg {
             Ok(Message::Text(text)) => {
-                println!("Received text message: {}", text);
-                app_handle.emit("ws-message", text).unwrap_or_default();
+                match serde_json::from_str::<Envelope>(&text) {
+                    Ok(envelope) => {
+                        if let Some(message) = handle_envelope(envelope, &app_handle, &state, &outbound, &encoding_tx) {
+                            upgrade_required = Some(message);
+                        }
+                    }
+                    // Not a protocol message; show it as-is
+                    Err(_) => {
+                        app_handle.emit("ws-message", text).unwrap_or_default();
+                    }
+                }
+            }
+            Ok(Message::Binary(data)) => {
+                let encoding = *encoding_tx.borrow();
+                match encoding.decode_binary::<Envelope>(&data) {
+                    Ok(envelope) => {
+                        if let Some(message) = handle_envelope(envelope, &app_handle, &state, &outbound, &encoding_tx) {
+                            upgrade_required = Some(message);
+                        }
+                    }
+                    Err(e) => log!("Failed to decode {} byte message: {}", data.len(), e),
+                }
             }
             Ok(Message::Ping(data)) => {
-                println!("Received ping, sending pong!");
                 let mut sink = ws_sink.lock().await;
                 if let Err(e) = sink.send(Message::Pong(data)).await {
-                    println!("Failed to send pong: {}", e);
-                    break;
+                    log!("Failed to send pong: {}", e);
+                    break format!("Failed to send pong: {}", e);
                 }
             }
-            Ok(Message::Pong(_)) => {
-                println!("Received pong from server!");
-                app_handle.emit("ws-message", "Received PONG from server").unwrap_or_default();
-            }
+            Ok(Message::Pong(_)) => state.diagnostics.pong_received(),
             Ok(Message::Close(_)) => {
-                println!("Server closed connection");
-                break;
+                log!("Server closed connection");
+                break "Server closed the connection".to_string();
             }
             Err(e) => {
-                println!("WebSocket error: {}", e);
-                break;
+                log!("WebSocket error: {}", e);
+                break e.to_string();
             }
             _ => {}
         }
-    }
+    };
 
     // Clean up
     send_task.abort();
+    match upgrade_required {
+        Some(message) => ConnectionEnd::UpgradeRequired(message),
+        None => ConnectionEnd::Disconnected(reason),
+    }
 }
 
-async fn get_warp_token(server_url: &str) -> Option<String> {
-    let client = reqwest::Client::new();
-    
-    println!("Checking WARP status for: {}", server_url);
-    
-    // Try to make a request through WARP
-    match client.head(server_url)
-        .send()
-        .await {
-            Ok(response) => {
-                println!("WARP check response status: {}", response.status());
-                println!("WARP check headers: {:?}", response.headers());
-                
-                // Check for WARP token in headers
-                if let Some(token) = response.headers().get("CF-Access-Jwt-Assertion") {
-                    println!("Found WARP token");
-                    return Some(token.to_str().unwrap_or_default().to_string());
-                }
-
-                // Also check Cookies for CF_Authorization
-                if let Some(cookies) = response.headers().get("set-cookie") {
-                    if let Ok(cookie_str) = cookies.to_str() {
-                        if cookie_str.contains("CF_Authorization=") {
-                            println!("Found CF_Authorization cookie");
-                            if let Some(token) = cookie_str
-                                .split(';')
-                                .find(|s| s.trim().starts_with("CF_Authorization="))
-                                .and_then(|s| s.split('=').nth(1)) {
-                                return Some(token.to_string());
-                            }
-                        }
-                    }
-                }
-            }
-            Err(e) => {
-                println!("Failed to get WARP token: {}", e);
-            }
-        }
-    
-    println!("No WARP token found");
-    None
-}
-
-// Helper function to generate WebSocket key
-fn generate_key() -> String {
-    use base64::{engine::general_purpose::STANDARD, Engine};
-    use rand::Rng;
-    
-    let mut key = [0u8; 16];
-    rand::thread_rng().fill(&mut key);
-    STANDARD.encode(key)
+/// Waits for the user to pick a different server profile.
+async fn profile_changed(profile_rx: &mut watch::Receiver<ServerProfile>) {
+    if profile_rx.changed().await.is_err() {
+        // The profile store is gone; there is nothing left to switch to
+        std::future::pending::<()>().await;
+    }
 }
 
 async fn connect_websocket(
     app_handle: tauri::AppHandle,
-    state: Arc<WebSocketState>
+    state: Arc<WebSocketState>,
+    mut profile_rx: watch::Receiver<ServerProfile>,
 ) {
-    let server_url = "https://rover.furcondispatch.org:3031";
-    let ws_url = "wss://rover.furcondispatch.org:3031/ws";
+    let mut profile = profile_rx.borrow_and_update().clone();
+    let mut credentials = Credentials::new(&profile.server_url);
+    let mut backoff = Backoff::default();
     
     loop {
-        println!("Attempting to connect to {}", ws_url);
-        app_handle.emit("ws-status", "connecting").unwrap_or_default();
-
-        let token = get_warp_token(server_url).await;
-
-        let ws_url = match Url::parse(ws_url) {
-            Ok(url) => url,
-            Err(e) => {
-                println!("Failed to parse WebSocket URL: {}", e);
-                continue;
+        // Switch servers if the user picked another profile since the last attempt.
+        // Compared rather than checked with `has_changed`, as waking on the
+        // change has already marked it seen.
+        let active = profile_rx.borrow_and_update().clone();
+        if active != profile {
+            if active.server_url != profile.server_url {
+                cache::switch_server(&app_handle, &state.local, &active.server_url);
             }
-        };
+            profile = active;
+            credentials = Credentials::new(&profile.server_url);
+            backoff.reset();
+        }
+        let ws_url = profile.ws_url();
+        app_handle.emit("ws-profile", &profile).unwrap_or_default();
 
-        let mut request = Request::new(Method::GET, ws_url.clone());
-
-        // Add required WebSocket headers
-        let headers = request.headers_mut();
-        headers.insert("Host", HeaderValue::from_str(ws_url.host_str().unwrap_or_default()).unwrap());
-        headers.insert("Upgrade", HeaderValue::from_static("websocket"));
-        headers.insert("Connection", HeaderValue::from_static("Upgrade"));
-        headers.insert("Sec-WebSocket-Version", HeaderValue::from_static("13"));
-        headers.insert("Sec-WebSocket-Key", HeaderValue::from_str(&generate_key()).unwrap());
-        headers.insert("Sec-WebSocket-Protocol", HeaderValue::from_static("rust-websocket"));
-        
-        // Add authorization if we have a token
-        if let Some(token) = &token {
-            println!("Adding authorization token to request");
-            if let Ok(auth_value) = HeaderValue::from_str(&format!("Bearer {}", token)) {
-                headers.insert("Authorization", auth_value);
-            }
+        log!("Attempting to connect to {} ({})", ws_url, profile.name);
+        emit_status(&app_handle, ConnectionStatus::new(ConnectionState::Authenticating));
+        app_handle.emit("ws-auth", AuthStatus::Authenticating).unwrap_or_default();
 
-            // Also add as cookie just in case
-            if let Ok(cookie_value) = HeaderValue::from_str(&format!("CF_Authorization={}", token)) {
-                headers.insert("Cookie", cookie_value);
-            }
-        } else {
-            println!("No authorization token available");
+        state.diagnostics.attempt();
+        let token = credentials.token().await;
+        if token.is_none() {
+            log!("No authorization token available");
         }
 
-        println!("Request headers: {:?}", headers);
-
-        match connect_async(ws_url.as_str()).await {
-            Ok((ws_stream, _)) => {
-                println!("WebSocket connected successfully!");
-                app_handle.emit("ws-status", "connected").unwrap_or_default();
+        emit_status(&app_handle, ConnectionStatus::new(ConnectionState::Connecting));
+        let mut min_delay = Duration::ZERO;
+        let reason = match auth::connect(&ws_url, token.as_deref()).await {
+            Ok(ws_stream) => {
+                log!("WebSocket connected successfully!");
+                state.diagnostics.connected();
+                app_handle.emit("ws-auth", AuthStatus::Authenticated).unwrap_or_default();
+                let connected_at = Instant::now();
                 
                 // Create channel for sending messages to WebSocket
                 let (tx, rx) = mpsc::channel(32);
@@ -207,49 +375,182 @@ async fn connect_websocket(
                 // Store sender in state
                 {
                     let mut tx_lock = state.tx.lock().await;
-                    *tx_lock = Some(tx);
+                    *tx_lock = Some(tx.clone());
                 }
                 
-                // Handle messages
-                handle_ws_messages(ws_stream, rx, app_handle.clone()).await;
+                // Handle messages until the connection drops or the user asks for another one
+                let end = tokio::select! {
+                    end = handle_ws_messages(ws_stream, tx, rx, app_handle.clone(), state.clone()) => Some(end),
+                    _ = profile_changed(&mut profile_rx) => None,
+                    _ = state.reconnect.notified() => None,
+                };
                 
                 // Clear sender from state
                 let mut tx_lock = state.tx.lock().await;
                 *tx_lock = None;
+                drop(tx_lock);
+                // Replies to anything still outstanding went down with the connection
+                state.requests.fail_all();
+                state.diagnostics.disconnected();
+                // Keep showing what we had, marked as out of date
+                if let Some(change) = state.local.mark_stale() {
+                    app_handle.emit("state-change", change).unwrap_or_default();
+                }
                 
-                println!("WebSocket connection ended");
-                app_handle.emit("ws-status", "disconnected").unwrap_or_default();
+                log!("WebSocket connection ended");
+                if connected_at.elapsed() >= STABLE_AFTER {
+                    backoff.reset();
+                }
+                match end {
+                    // Reconnecting was the user's idea, so don't make them wait
+                    None => continue,
+                    Some(ConnectionEnd::UpgradeRequired(message)) => {
+                        state.diagnostics.error(&message);
+                        emit_status(
+                            &app_handle,
+                            ConnectionStatus::new(ConnectionState::Offline).with_reason(message),
+                        );
+                        // Another profile may point at a server we can still talk to
+                        tokio::select! {
+                            _ = profile_changed(&mut profile_rx) => {}
+                            _ = state.reconnect.notified() => {}
+                        }
+                        continue;
+                    }
+                    Some(ConnectionEnd::Disconnected(reason)) => reason,
+                }
+            }
+            Err(HandshakeError::Unauthorized) => {
+                // Fetch a fresh token before the next attempt
+                log!("Server rejected our credentials");
+                credentials.invalidate();
+                let status = if token.is_some() { AuthStatus::Expired } else { AuthStatus::Unauthenticated };
+                app_handle.emit("ws-auth", status).unwrap_or_default();
+                HandshakeError::Unauthorized.to_string()
+            }
+            Err(HandshakeError::Forbidden) => {
+                // Only an administrator can fix this, so don't hammer the server
+                log!("Account is not allowed to connect");
+                app_handle.emit("ws-auth", AuthStatus::Forbidden).unwrap_or_default();
+                min_delay = FORBIDDEN_RETRY;
+                HandshakeError::Forbidden.to_string()
             }
             Err(e) => {
-                println!("Failed to connect to WebSocket: {}", e);
-                app_handle.emit("ws-status", "disconnected").unwrap_or_default();
+                log!("Failed to connect to WebSocket: {}", e);
+                e.to_string()
             }
-        }
+        };
+
+        state.diagnostics.error(&reason);
 
-        // Wait before reconnecting
-        tokio::time::sleep(std::time::Duration::from_secs(2)).await;
+        // Wait before reconnecting, unless the user picks a server or asks to retry now
+        let delay = backoff.next_delay().max(min_delay);
+        log!("Reconnecting in {:?}", delay);
+        emit_status(
+            &app_handle,
+            ConnectionStatus::new(ConnectionState::Offline)
+                .with_reason(reason)
+                .retrying_in(delay),
+        );
+        tokio::select! {
+            _ = tokio::time::sleep(delay) => {}
+            _ = profile_changed(&mut profile_rx) => {}
+            _ = state.reconnect.notified() => {}
+        }
     }
 }
 
-
 fn main() {
-    let state = Arc::new(WebSocketState {
-        tx: Arc::new(Mutex::new(None)),
-    });
+    let state = Arc::new(WebSocketState::default());
     let state_clone = state.clone();
 
     tauri::Builder::default()
         .manage(WebSocketState {
             tx: state.tx.clone(),
+            reconnect: state.reconnect.clone(),
+            requests: state.requests.clone(),
+            local: state.local.clone(),
+            diagnostics: state.diagnostics.clone(),
+        })
+        .plugin(tauri_plugin_store::Builder::new().build())
+        .plugin(tauri_plugin_notification::init())
+        .plugin(tauri_plugin_window_state::Builder::default().build())
+        .invoke_handler(tauri::generate_handler![
+            reconnect_now,
+            commands::get_local_state,
+            commands::send_ping,
+            commands::list_events,
+            commands::create_event,
+            commands::set_active_event,
+            commands::get_active_calls,
+            commands::get_event_calls,
+            commands::get_calls_on_day,
+            commands::get_call,
+            commands::create_call,
+            commands::update_call,
+            commands::add_note,
+            commands::get_call_natures,
+            commands::get_pending_queue,
+            commands::recommend_units,
+            commands::dispatch_unit,
+            commands::get_units,
+            commands::update_unit_status,
+            commands::check_in_unit,
+            commands::update_unit_location,
+            commands::update_unit_position,
+            commands::activate_emergency,
+            commands::acknowledge_emergency,
+            commands::clear_emergency,
+            commands::start_roll_call,
+            commands::mark_unit_accounted,
+            commands::realert_roll_call,
+            commands::complete_roll_call,
+            commands::get_roll_calls,
+            commands::get_locations,
+            commands::resolve_location,
+            commands::create_location,
+            commands::set_location_position,
+            commands::import_map_layer,
+            commands::get_map_layers,
+            commands::create_location_link,
+            commands::get_location_links,
+            commands::get_route,
+            outbox::list_outbox,
+            outbox::discard_outbox_item,
+            profiles::list_profiles,
+            profiles::save_profile,
+            profiles::delete_profile,
+            profiles::set_active_profile,
+            windows::open_pop_out,
+            diagnostics::get_diagnostics,
+            diagnostics::save_diagnostics_bundle,
+        ])
+        .on_window_event(|window, event| {
+            if let tauri::WindowEvent::CloseRequested { .. } = event {
+                if window.label() == "main" {
+                    // Pop-outs close with the console and are reopened next time
+                    window.app_handle().exit(0);
+                } else {
+                    window.state::<windows::PopOuts>().closed(window.label());
+                }
+            }
         })
-        .invoke_handler(tauri::generate_handler![send_ping])
         .setup(|app| {
             let app_handle = app.handle().clone();
+            let profiles = Profiles::load(&app_handle)?;
+            let profile_rx = profiles.watch();
+            // Show the last known state straight away, before any connection
+            cache::start(&app_handle, state_clone.local.clone(), profiles.watch());
+            // Before connecting, as every Welcome replays the outbox
+            app.manage(outbox::Outbox::load(&app_handle, profiles.watch())?);
+            app.manage(profiles);
+            app.manage(windows::PopOuts::load(&app_handle)?);
+            app.state::<windows::PopOuts>().restore(&app_handle, &state_clone.local);
             
             // Start WebSocket connection in background
             tauri::async_runtime::spawn(async move {
-                println!("Starting WebSocket connection...");
-                connect_websocket(app_handle, state_clone).await;
+                log!("Starting WebSocket connection...");
+                connect_websocket(app_handle, state_clone, profile_rx).await;
             });
             
             Ok(())
@@ -0,0 +1,388 @@
+//! Operations made while offline, kept on disk until the server has them.
+//!
+//! Commands that change dispatch state go through [`Outbox::deliver`]. When
+//! the client is connected and nothing is waiting, they are sent straight
+//! away; otherwise they join the queue, which survives restarts and is replayed
+//! in order after every reconnect. Items the server refuses on replay are
+//! marked conflicted and stay until the user discards them.
+//!
+//! Each item belongs to the server profile it was made against and is only
+//! replayed while that profile is active.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use shared_types::ProtocolMessage;
+use tauri::{AppHandle, Emitter, Manager, Wry};
+use tauri_plugin_store::{Store, StoreExt};
+use tokio::sync::watch;
+
+use crate::profiles::ServerProfile;
+use crate::requests::CommandError;
+use crate::WebSocketState;
+
+const STORE_PATH: &str = "outbox.json";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum OutboxStatus {
+    Pending,
+    Sent,
+    /// The server refused the operation when it was replayed
+    Conflicted,
+    /// The connection dropped after the operation was replayed, so the server
+    /// may or may not have applied it
+    Unknown,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OutboxItem {
+    pub id: u64,
+    /// Server the operation was made against
+    #[serde(default)]
+    pub server_url: String,
+    /// Unix time in milliseconds when the operation was made
+    pub created_at: i64,
+    pub message: ProtocolMessage,
+    pub status: OutboxStatus,
+    /// The server's reason, for conflicted items
+    pub error: Option<String>,
+}
+
+/// What happened to an operation handed to [`Outbox::deliver`].
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "outcome", content = "value", rename_all = "kebab-case")]
+pub enum Delivery<T> {
+    /// The server applied it and replied
+    Done(T),
+    /// Queued as the outbox item with this ID
+    Queued(u64),
+}
+
+impl<T> Delivery<T> {
+    pub fn try_map<U, E>(self, f: impl FnOnce(T) -> Result<U, E>) -> Result<Delivery<U>, E> {
+        match self {
+            Delivery::Done(value) => f(value).map(Delivery::Done),
+            Delivery::Queued(id) => Ok(Delivery::Queued(id)),
+        }
+    }
+}
+
+/// The queued items, apart from saving them and telling the windows.
+#[derive(Debug, Default)]
+struct Queue {
+    items: Vec<OutboxItem>,
+    /// Never reused, so a discarded item's ID can't come back
+    next_id: u64,
+}
+
+impl Queue {
+    fn push(&mut self, server_url: String, message: ProtocolMessage) -> u64 {
+        let id = self.next_id.max(1);
+        self.next_id = id + 1;
+        self.items.push(OutboxItem {
+            id,
+            server_url,
+            created_at: chrono::Utc::now().timestamp_millis(),
+            message,
+            status: OutboxStatus::Pending,
+            error: None,
+        });
+        id
+    }
+
+    /// The oldest item waiting to be sent to `server_url`.
+    fn next_pending(&self, server_url: &str) -> Option<&OutboxItem> {
+        self.items
+            .iter()
+            .find(|item| item.status == OutboxStatus::Pending && item.server_url == server_url)
+    }
+
+    /// Records how replaying an item went. Returns whether replay has to stop
+    /// until the next reconnect.
+    fn replayed(&mut self, id: u64, result: Result<(), CommandError>) -> bool {
+        let (status, error, stop) = match result {
+            Ok(()) => (OutboxStatus::Sent, None, false),
+            Err(e @ (CommandError::Server(_) | CommandError::UnexpectedReply(_))) => {
+                (OutboxStatus::Conflicted, Some(e.to_string()), false)
+            }
+            // Sending it again could apply it twice, so leave it to the user
+            Err(e @ (CommandError::Disconnected | CommandError::Timeout)) => {
+                log!("Outbox replay stopped: {}", e);
+                (OutboxStatus::Unknown, Some(e.to_string()), true)
+            }
+            // Try again after the next reconnect
+            Err(e) => {
+                log!("Outbox replay stopped: {}", e);
+                return true;
+            }
+        };
+        if let Some(item) = self.items.iter_mut().find(|item| item.id == id) {
+            item.status = status;
+            item.error = error;
+        }
+        stop
+    }
+
+    /// Removes an item that is no longer pending. Pending items can't be
+    /// discarded, as they may be on their way to the server.
+    fn discard(&mut self, id: u64) -> Result<(), String> {
+        match self.items.iter().position(|item| item.id == id) {
+            Some(index) if self.items[index].status == OutboxStatus::Pending => {
+                Err("This operation has not been sent yet".to_string())
+            }
+            Some(index) => {
+                self.items.remove(index);
+                Ok(())
+            }
+            None => Err(format!("No queued operation {}", id)),
+        }
+    }
+}
+
+pub struct Outbox {
+    app: AppHandle,
+    store: Arc<Store<Wry>>,
+    profile_rx: watch::Receiver<ServerProfile>,
+    queue: Mutex<Queue>,
+    flushing: AtomicBool,
+}
+
+impl Outbox {
+    /// Loads the queue left by the last run. Items that were already sent are dropped.
+    pub fn load(app: &AppHandle, profile_rx: watch::Receiver<ServerProfile>) -> Result<Self, String> {
+        let store = app.store(STORE_PATH).map_err(|e| e.to_string())?;
+        let mut items: Vec<OutboxItem> = store
+            .get("items")
+            .and_then(|value| serde_json::from_value(value).ok())
+            .unwrap_or_default();
+        items.retain(|item| item.status != OutboxStatus::Sent);
+        // Items queued before they were tied to a server were made against the active one
+        let server_url = profile_rx.borrow().server_url.clone();
+        for item in items.iter_mut().filter(|item| item.server_url.is_empty()) {
+            item.server_url = server_url.clone();
+        }
+        let next_id = store
+            .get("next_id")
+            .and_then(|value| value.as_u64())
+            .unwrap_or(1)
+            .max(items.iter().map(|item| item.id + 1).max().unwrap_or(1));
+
+        Ok(Self {
+            app: app.clone(),
+            store,
+            profile_rx,
+            queue: Mutex::new(Queue { items, next_id }),
+            flushing: AtomicBool::new(false),
+        })
+    }
+
+    pub fn items(&self) -> Vec<OutboxItem> {
+        self.queue.lock().unwrap().items.clone()
+    }
+
+    fn server_url(&self) -> String {
+        self.profile_rx.borrow().server_url.clone()
+    }
+
+    /// Whether anything is waiting to be sent to the active server.
+    fn has_pending(&self) -> bool {
+        self.queue.lock().unwrap().next_pending(&self.server_url()).is_some()
+    }
+
+    /// Changes the queue, then saves it and tells every window about it.
+    fn update<T>(&self, f: impl FnOnce(&mut Queue) -> T) -> T {
+        let mut queue = self.queue.lock().unwrap();
+        let result = f(&mut queue);
+        self.store.set("items", json!(queue.items));
+        self.store.set("next_id", json!(queue.next_id));
+        if let Err(e) = self.store.save() {
+            log!("Failed to save outbox: {}", e);
+        }
+        self.app.emit("outbox-changed", &queue.items).unwrap_or_default();
+        result
+    }
+
+    pub fn discard(&self, id: u64) -> Result<(), String> {
+        self.update(|queue| queue.discard(id))
+    }
+
+    /// Sends `message` now if possible, or queues it behind anything already
+    /// waiting so operations reach the server in the order they were made.
+    ///
+    /// A request that was sent but lost its connection before the reply is not
+    /// queued, since the server may already have applied it.
+    pub async fn deliver(
+        &self,
+        ws: &WebSocketState,
+        message: ProtocolMessage,
+    ) -> Result<Delivery<ProtocolMessage>, CommandError> {
+        if !self.has_pending() {
+            match ws.request(message.clone()).await {
+                Err(CommandError::NotConnected) => {}
+                reply => return reply.map(Delivery::Done),
+            }
+        }
+        let server_url = self.server_url();
+        let id = self.update(|queue| queue.push(server_url, message));
+        // We may be connected with an earlier replay having stopped part way
+        start_flush(&self.app);
+        Ok(Delivery::Queued(id))
+    }
+
+    /// Replays the active server's pending items in order until none are left
+    /// or the connection fails again.
+    async fn flush(&self, ws: &WebSocketState) {
+        // Only one replay at a time; if an item was queued just as the last
+        // replay finished, go round again
+        while !self.flushing.swap(true, Ordering::AcqRel) {
+            let interrupted = self.replay(ws).await;
+            self.flushing.store(false, Ordering::Release);
+            if interrupted || !self.has_pending() {
+                break;
+            }
+        }
+    }
+
+    /// Returns whether replay stopped because the server could not be reached.
+    async fn replay(&self, ws: &WebSocketState) -> bool {
+        loop {
+            let next = self
+                .queue
+                .lock()
+                .unwrap()
+                .next_pending(&self.server_url())
+                .map(|item| (item.id, item.message.clone()));
+            let Some((id, message)) = next else {
+                return false;
+            };
+
+            let result = ws.request(message).await.map(|_| ());
+            if self.update(|queue| queue.replayed(id, result)) {
+                return true;
+            }
+        }
+    }
+}
+
+/// Replays the outbox in the background.
+pub fn start_flush(app: &AppHandle) {
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        let outbox = app.state::<Outbox>();
+        let ws = app.state::<WebSocketState>();
+        outbox.flush(&ws).await;
+    });
+}
+
+#[tauri::command]
+pub fn list_outbox(outbox: tauri::State<'_, Outbox>) -> Vec<OutboxItem> {
+    outbox.items()
+}
+
+#[tauri::command]
+pub fn discard_outbox_item(outbox: tauri::State<'_, Outbox>, id: u64) -> Result<(), String> {
+    outbox.discard(id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ACTIVE: &str = "https://dispatch.example";
+    const OTHER: &str = "https://other.example";
+
+    fn queue(servers: &[&str]) -> Queue {
+        let mut queue = Queue::default();
+        for server_url in servers {
+            queue.push(server_url.to_string(), ProtocolMessage::Ping);
+        }
+        queue
+    }
+
+    fn status(queue: &Queue, id: u64) -> OutboxStatus {
+        queue.items.iter().find(|item| item.id == id).unwrap().status
+    }
+
+    /// Runs replay against the queue the way `Outbox::replay` does, with
+    /// `reply` standing in for the server.
+    fn replay(
+        queue: &mut Queue,
+        server_url: &str,
+        mut reply: impl FnMut(u64) -> Result<(), CommandError>,
+    ) -> Vec<u64> {
+        let mut sent = Vec::new();
+        while let Some(id) = queue.next_pending(server_url).map(|item| item.id) {
+            sent.push(id);
+            if queue.replayed(id, reply(id)) {
+                break;
+            }
+        }
+        sent
+    }
+
+    #[test]
+    fn only_the_active_servers_items_are_replayed() {
+        let mut queue = queue(&[ACTIVE, OTHER, ACTIVE]);
+
+        assert_eq!(replay(&mut queue, ACTIVE, |_| Ok(())), vec![1, 3]);
+        assert_eq!(status(&queue, 1), OutboxStatus::Sent);
+        assert_eq!(status(&queue, 2), OutboxStatus::Pending);
+        assert_eq!(status(&queue, 3), OutboxStatus::Sent);
+
+        assert_eq!(replay(&mut queue, OTHER, |_| Ok(())), vec![2]);
+    }
+
+    #[test]
+    fn a_lost_reply_leaves_the_item_unknown_and_stops_replay() {
+        let mut queue = queue(&[ACTIVE, ACTIVE]);
+
+        let sent = replay(&mut queue, ACTIVE, |_| Err(CommandError::Disconnected));
+
+        assert_eq!(sent, vec![1]);
+        assert_eq!(status(&queue, 1), OutboxStatus::Unknown);
+        assert_eq!(status(&queue, 2), OutboxStatus::Pending);
+        assert!(queue.next_pending(ACTIVE).is_some());
+    }
+
+    #[test]
+    fn a_refused_item_is_conflicted_and_replay_goes_on() {
+        let mut queue = queue(&[ACTIVE, ACTIVE]);
+
+        let sent = replay(&mut queue, ACTIVE, |id| match id {
+            1 => Err(CommandError::Server("call is closed".to_string())),
+            _ => Ok(()),
+        });
+
+        assert_eq!(sent, vec![1, 2]);
+        assert_eq!(status(&queue, 1), OutboxStatus::Conflicted);
+        assert_eq!(queue.items[0].error.as_deref(), Some("call is closed"));
+        assert_eq!(status(&queue, 2), OutboxStatus::Sent);
+    }
+
+    #[test]
+    fn not_being_connected_leaves_the_item_pending() {
+        let mut queue = queue(&[ACTIVE]);
+
+        replay(&mut queue, ACTIVE, |_| Err(CommandError::NotConnected));
+
+        assert_eq!(status(&queue, 1), OutboxStatus::Pending);
+    }
+
+    #[test]
+    fn only_finished_items_can_be_discarded_and_ids_are_not_reused() {
+        let mut queue = queue(&[ACTIVE, ACTIVE]);
+        replay(&mut queue, ACTIVE, |id| match id {
+            2 => Err(CommandError::Server("no".to_string())),
+            _ => Ok(()),
+        });
+        queue.push(ACTIVE.to_string(), ProtocolMessage::Ping);
+
+        assert!(queue.discard(3).is_err());
+        assert!(queue.discard(2).is_ok());
+        assert!(queue.discard(2).is_err());
+        assert_eq!(queue.push(ACTIVE.to_string(), ProtocolMessage::Ping), 4);
+    }
+}
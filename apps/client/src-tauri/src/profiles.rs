@@ -0,0 +1,184 @@
+//! Named dispatch servers (production, training, a local dev server, ...) the
+//! client can switch between at runtime, persisted with the store plugin.
+
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use tauri::{AppHandle, Url, Wry};
+use tauri_plugin_store::{Store, StoreExt};
+use tokio::sync::watch;
+
+const STORE_PATH: &str = "profiles.json";
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ServerProfile {
+    pub name: String,
+    /// Base HTTPS (or HTTP, for local development) URL of the server
+    pub server_url: String,
+}
+
+impl ServerProfile {
+    /// The server's WebSocket endpoint, e.g. `wss://host:3031/ws`.
+    pub fn ws_url(&self) -> String {
+        let base = self.server_url.trim_end_matches('/');
+        let base = match base.split_once("://") {
+            Some(("https", rest)) => format!("wss://{}", rest),
+            Some(("http", rest)) => format!("ws://{}", rest),
+            _ => base.to_string(),
+        };
+        format!("{}/ws", base)
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        if self.name.trim().is_empty() {
+            return Err("Profile name is required".to_string());
+        }
+        let url = Url::parse(&self.server_url).map_err(|e| format!("Invalid server URL: {}", e))?;
+        if !matches!(url.scheme(), "https" | "http") {
+            return Err("Server URL must start with https:// or http://".to_string());
+        }
+        Ok(())
+    }
+}
+
+fn default_profiles() -> Vec<ServerProfile> {
+    vec![
+        ServerProfile {
+            name: "Production".to_string(),
+            server_url: "https://rover.furcondispatch.org:3031".to_string(),
+        },
+        ServerProfile {
+            name: "Training".to_string(),
+            server_url: "https://training.rover.furcondispatch.org:3031".to_string(),
+        },
+        ServerProfile {
+            name: "Local".to_string(),
+            server_url: "http://localhost:3031".to_string(),
+        },
+    ]
+}
+
+/// Every profile plus the one the client is using, for the settings screen.
+#[derive(Debug, Clone, Serialize)]
+pub struct ProfileList {
+    pub profiles: Vec<ServerProfile>,
+    pub active: String,
+}
+
+/// Saved profiles and the active one, which the connection loop follows.
+pub struct Profiles {
+    store: Arc<Store<Wry>>,
+    active: watch::Sender<ServerProfile>,
+}
+
+impl Profiles {
+    pub fn load(app: &AppHandle) -> Result<Self, String> {
+        let store = app.store(STORE_PATH).map_err(|e| e.to_string())?;
+        let profiles: Vec<ServerProfile> = store
+            .get("profiles")
+            .and_then(|value| serde_json::from_value(value).ok())
+            .filter(|profiles: &Vec<ServerProfile>| !profiles.is_empty())
+            .unwrap_or_else(default_profiles);
+        let active_name = store.get("active").and_then(|value| value.as_str().map(str::to_string));
+        let active = active_name
+            .and_then(|name| profiles.iter().find(|profile| profile.name == name).cloned())
+            .unwrap_or_else(|| profiles[0].clone());
+
+        let profiles_state = Self {
+            store,
+            active: watch::Sender::new(active),
+        };
+        profiles_state.persist(&profiles)?;
+        Ok(profiles_state)
+    }
+
+    fn profiles(&self) -> Vec<ServerProfile> {
+        self.store
+            .get("profiles")
+            .and_then(|value| serde_json::from_value(value).ok())
+            .unwrap_or_else(default_profiles)
+    }
+
+    fn persist(&self, profiles: &[ServerProfile]) -> Result<(), String> {
+        self.store.set("profiles", json!(profiles));
+        self.store.set("active", self.active.borrow().name.clone());
+        self.store.save().map_err(|e| e.to_string())
+    }
+
+    pub fn list(&self) -> ProfileList {
+        ProfileList {
+            profiles: self.profiles(),
+            active: self.active.borrow().name.clone(),
+        }
+    }
+
+    /// Follows the active profile; the connection loop reconnects when it changes.
+    pub fn watch(&self) -> watch::Receiver<ServerProfile> {
+        self.active.subscribe()
+    }
+
+    /// Adds a profile, or replaces the one with the same name.
+    pub fn save_profile(&self, profile: ServerProfile) -> Result<(), String> {
+        profile.validate()?;
+        let mut profiles = self.profiles();
+        match profiles.iter_mut().find(|existing| existing.name == profile.name) {
+            Some(existing) => *existing = profile.clone(),
+            None => profiles.push(profile.clone()),
+        }
+        // Editing the active profile takes effect immediately
+        self.active.send_if_modified(|active| {
+            let changed = active.name == profile.name && *active != profile;
+            if changed {
+                *active = profile;
+            }
+            changed
+        });
+        self.persist(&profiles)
+    }
+
+    pub fn delete_profile(&self, name: &str) -> Result<(), String> {
+        if self.active.borrow().name == name {
+            return Err("Switch to another server before deleting this one".to_string());
+        }
+        let mut profiles = self.profiles();
+        profiles.retain(|profile| profile.name != name);
+        self.persist(&profiles)
+    }
+
+    pub fn set_active(&self, name: &str) -> Result<ServerProfile, String> {
+        let profile = self
+            .profiles()
+            .into_iter()
+            .find(|profile| profile.name == name)
+            .ok_or_else(|| format!("No server profile named {}", name))?;
+        self.active.send_if_modified(|active| {
+            let changed = *active != profile;
+            *active = profile.clone();
+            changed
+        });
+        self.persist(&self.profiles())?;
+        Ok(profile)
+    }
+}
+
+#[tauri::command]
+pub fn list_profiles(profiles: tauri::State<'_, Profiles>) -> ProfileList {
+    profiles.list()
+}
+
+#[tauri::command]
+pub fn save_profile(profiles: tauri::State<'_, Profiles>, profile: ServerProfile) -> Result<(), String> {
+    profiles.save_profile(profile)
+}
+
+#[tauri::command]
+pub fn delete_profile(profiles: tauri::State<'_, Profiles>, name: String) -> Result<(), String> {
+    profiles.delete_profile(&name)
+}
+
+/// Switches servers; the client disconnects and connects to the new one.
+#[tauri::command]
+pub fn set_active_profile(profiles: tauri::State<'_, Profiles>, name: String) -> Result<ServerProfile, String> {
+    profiles.set_active(&name)
+}
@@ -0,0 +1,152 @@
+//! Matches the server's replies to the requests that caused them.
+//!
+//! Every request goes out in an `Envelope` with a fresh `request_id`; the
+//! server copies it onto its reply, so replies can be told apart from the
+//! broadcasts that arrive in between.
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Mutex;
+
+use serde::Serialize;
+use shared_types::{Envelope, ProtocolMessage};
+use tokio::sync::oneshot;
+
+/// Why a command failed, as reported to the frontend.
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "kind", content = "message", rename_all = "kebab-case")]
+pub enum CommandError {
+    /// An argument could not be turned into a request
+    InvalidArgument(String),
+    /// There is no connection to send the request on
+    NotConnected,
+    /// The connection dropped before the server replied
+    Disconnected,
+    /// The server did not reply in time
+    Timeout,
+    /// The server refused the request
+    Server(String),
+    /// The server replied with something other than what the command expects
+    UnexpectedReply(String),
+}
+
+impl CommandError {
+    pub fn unexpected(reply: &ProtocolMessage) -> Self {
+        CommandError::UnexpectedReply(reply.message_type())
+    }
+}
+
+impl std::fmt::Display for CommandError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CommandError::InvalidArgument(message) => write!(f, "{}", message),
+            CommandError::NotConnected => write!(f, "not connected to the dispatch server"),
+            CommandError::Disconnected => write!(f, "connection lost before the server replied"),
+            CommandError::Timeout => write!(f, "the server did not reply in time"),
+            CommandError::Server(message) => write!(f, "{}", message),
+            CommandError::UnexpectedReply(name) => write!(f, "unexpected reply from server: {}", name),
+        }
+    }
+}
+
+/// Requests awaiting a reply on the current connection.
+#[derive(Default)]
+pub struct Requests {
+    next_id: AtomicU32,
+    pending: Mutex<HashMap<u32, oneshot::Sender<ProtocolMessage>>>,
+}
+
+impl Requests {
+    /// Allocates a request ID and the channel its reply will arrive on.
+    pub fn register(&self) -> (u32, oneshot::Receiver<ProtocolMessage>) {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let (tx, rx) = oneshot::channel();
+        self.pending.lock().unwrap().insert(id, tx);
+        (id, rx)
+    }
+
+    /// Stops waiting for a request that was never sent or has timed out.
+    pub fn cancel(&self, id: u32) {
+        self.pending.lock().unwrap().remove(&id);
+    }
+
+    /// Hands a reply to the request waiting for it. Returns the envelope if no
+    /// request is waiting, as for broadcasts.
+    pub fn resolve(&self, envelope: Envelope) -> Option<Envelope> {
+        let Some(id) = envelope.request_id else {
+            return Some(envelope);
+        };
+        match self.pending.lock().unwrap().remove(&id) {
+            Some(reply) => {
+                let _ = reply.send(envelope.message);
+                None
+            }
+            None => Some(envelope),
+        }
+    }
+
+    /// Number of requests still waiting for a reply.
+    pub fn pending_count(&self) -> usize {
+        self.pending.lock().unwrap().len()
+    }
+
+    /// Fails every outstanding request once the connection has dropped.
+    pub fn fail_all(&self) {
+        self.pending.lock().unwrap().clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn replies_reach_the_request_that_caused_them() {
+        let requests = Requests::default();
+        let (first, mut first_rx) = requests.register();
+        let (second, mut second_rx) = requests.register();
+        assert_ne!(first, second);
+
+        assert!(requests.resolve(Envelope::reply(Some(second), ProtocolMessage::Ack)).is_none());
+        assert!(matches!(second_rx.try_recv(), Ok(ProtocolMessage::Ack)));
+        assert!(first_rx.try_recv().is_err());
+        assert_eq!(requests.pending_count(), 1);
+
+        let error = ProtocolMessage::Error { message: "nope".to_string() };
+        assert!(requests.resolve(Envelope::reply(Some(first), error)).is_none());
+        assert!(matches!(first_rx.try_recv(), Ok(ProtocolMessage::Error { .. })));
+        assert_eq!(requests.pending_count(), 0);
+    }
+
+    #[test]
+    fn broadcasts_and_unknown_replies_are_passed_on() {
+        let requests = Requests::default();
+        let (_, _rx) = requests.register();
+
+        let broadcast = requests.resolve(Envelope::from(ProtocolMessage::Ping));
+        assert!(matches!(broadcast, Some(Envelope { request_id: None, .. })));
+        let stray = requests.resolve(Envelope::reply(Some(99), ProtocolMessage::Ack));
+        assert!(matches!(stray, Some(Envelope { request_id: Some(99), .. })));
+        assert_eq!(requests.pending_count(), 1);
+    }
+
+    #[test]
+    fn cancelled_requests_ignore_late_replies() {
+        let requests = Requests::default();
+        let (id, _rx) = requests.register();
+        requests.cancel(id);
+        assert!(requests.resolve(Envelope::reply(Some(id), ProtocolMessage::Ack)).is_some());
+    }
+
+    #[test]
+    fn fail_all_closes_every_waiting_request() {
+        let requests = Requests::default();
+        let (_, mut first_rx) = requests.register();
+        let (_, mut second_rx) = requests.register();
+        requests.fail_all();
+
+        assert_eq!(requests.pending_count(), 0);
+        assert!(matches!(first_rx.try_recv(), Err(oneshot::error::TryRecvError::Closed)));
+        assert!(matches!(second_rx.try_recv(), Err(oneshot::error::TryRecvError::Closed)));
+    }
+}
@@ -0,0 +1,138 @@
+//! Pop-out windows for a single incident or the unit board, for dispatchers
+//! spreading the console across monitors.
+//!
+//! Every window runs the same frontend and follows the same Rust-side state
+//! through `state-change` events, so a pop-out needs nothing of its own beyond
+//! its route. The pop-outs left open are remembered and reopened on the next
+//! start, where `tauri-plugin-window-state` puts each back where it was.
+
+use std::sync::{Arc, Mutex};
+
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use shared_types::id::IncidentId;
+use tauri::{AppHandle, Manager, WebviewUrl, WebviewWindowBuilder, Wry};
+use tauri_plugin_store::{Store, StoreExt};
+
+use crate::local_state::LocalState;
+
+const STORE_PATH: &str = "windows.json";
+
+/// What a pop-out window shows.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "panel", rename_all = "kebab-case")]
+pub enum PopOut {
+    Incident { id: IncidentId },
+    UnitBoard,
+}
+
+impl PopOut {
+    /// Window label, which is also the key its saved position is kept under.
+    fn label(&self) -> String {
+        match self {
+            PopOut::Incident { id } => format!("incident-{}", id),
+            PopOut::UnitBoard => "unit-board".to_string(),
+        }
+    }
+
+    fn route(&self) -> String {
+        match self {
+            PopOut::Incident { id } => format!("popout/incident/{}", id),
+            PopOut::UnitBoard => "popout/units".to_string(),
+        }
+    }
+
+    fn title(&self) -> &'static str {
+        match self {
+            PopOut::Incident { .. } => "Incident",
+            PopOut::UnitBoard => "Unit Board",
+        }
+    }
+}
+
+pub struct PopOuts {
+    store: Arc<Store<Wry>>,
+    open: Mutex<Vec<PopOut>>,
+}
+
+impl PopOuts {
+    pub fn load(app: &AppHandle) -> Result<Self, String> {
+        let store = app.store(STORE_PATH).map_err(|e| e.to_string())?;
+        let open = store
+            .get("open")
+            .and_then(|value| serde_json::from_value(value).ok())
+            .unwrap_or_default();
+        Ok(Self { store, open: Mutex::new(open) })
+    }
+
+    fn save(&self, open: &[PopOut]) {
+        self.store.set("open", json!(open));
+        if let Err(e) = self.store.save() {
+            log!("Failed to save window layout: {}", e);
+        }
+    }
+
+    /// Opens a pop-out, or brings it to the front if it is already open.
+    pub fn open(&self, app: &AppHandle, pop_out: PopOut) -> Result<(), String> {
+        let label = pop_out.label();
+        if let Some(window) = app.get_webview_window(&label) {
+            window.unminimize().map_err(|e| e.to_string())?;
+            return window.set_focus().map_err(|e| e.to_string());
+        }
+
+        WebviewWindowBuilder::new(app, &label, WebviewUrl::App(pop_out.route().into()))
+            .title(pop_out.title())
+            .inner_size(480.0, 640.0)
+            .build()
+            .map_err(|e| e.to_string())?;
+
+        let mut open = self.open.lock().unwrap();
+        if !open.contains(&pop_out) {
+            open.push(pop_out);
+            self.save(&open);
+        }
+        Ok(())
+    }
+
+    /// Forgets a pop-out the user closed, so it is not reopened next time.
+    pub fn closed(&self, label: &str) {
+        let mut open = self.open.lock().unwrap();
+        let before = open.len();
+        open.retain(|pop_out| pop_out.label() != label);
+        if open.len() != before {
+            self.save(&open);
+        }
+    }
+
+    /// Reopens the pop-outs that were open when the app last quit. Incidents
+    /// that are no longer in the local state, because they were cleared or
+    /// belong to another server or event, are forgotten instead.
+    pub fn restore(&self, app: &AppHandle, local: &LocalState) {
+        let open = {
+            let mut open = self.open.lock().unwrap();
+            let before = open.len();
+            open.retain(|pop_out| match pop_out {
+                PopOut::Incident { id } => local.call(*id).is_some(),
+                PopOut::UnitBoard => true,
+            });
+            if open.len() != before {
+                self.save(&open);
+            }
+            open.clone()
+        };
+        for pop_out in open {
+            if let Err(e) = self.open(app, pop_out) {
+                log!("Failed to reopen window: {}", e);
+            }
+        }
+    }
+}
+
+#[tauri::command]
+pub async fn open_pop_out(
+    app: AppHandle,
+    pop_outs: tauri::State<'_, PopOuts>,
+    pop_out: PopOut,
+) -> Result<(), String> {
+    pop_outs.open(&app, pop_out)
+}
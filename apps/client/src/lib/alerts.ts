@@ -0,0 +1,87 @@
+// Alert tones for the `alert` events raised by src-tauri/src/alerts.rs, and the
+// rules it reads from the settings store. Tones are synthesized so they play
+// without any audio files.
+import { listen } from '@tauri-apps/api/event'
+import type { UnitId } from './schema'
+
+export type AlertKind = 'high-priority-call' | 'call-escalated' | 'emergency' | 'assignment'
+
+export interface Alert {
+  kind: AlertKind
+  title: string
+  body: string
+  tone: string | null
+}
+
+export interface AlertPreference {
+  notify: boolean
+  tone: string | null
+}
+
+export interface AlertRules {
+  muted: boolean
+  /** Local times as `HH:MM`; may run past midnight */
+  quiet_hours: { start: string, end: string } | null
+  kinds: Partial<Record<AlertKind, AlertPreference>>
+  position_units: UnitId[]
+}
+
+export const alertKinds: Record<AlertKind, string> = {
+  'high-priority-call': 'New high priority calls',
+  'call-escalated': 'Escalated calls',
+  'emergency': 'Unit emergencies',
+  'assignment': 'Assignments to my position',
+}
+
+/** Must match the defaults in alerts.rs */
+export const defaultPreferences: Record<AlertKind, AlertPreference> = {
+  'high-priority-call': { notify: true, tone: 'chime' },
+  'call-escalated': { notify: true, tone: 'chime' },
+  'emergency': { notify: true, tone: 'alarm' },
+  'assignment': { notify: true, tone: 'beep' },
+}
+
+export const defaultRules: AlertRules = {
+  muted: false,
+  quiet_hours: null,
+  kinds: {},
+  position_units: [],
+}
+
+// Each tone is a list of [frequency in Hz, duration in seconds]; 0 Hz is a rest
+const tones: Record<string, [number, number][]> = {
+  chime: [[880, 0.15], [0, 0.05], [1320, 0.3]],
+  beep: [[1000, 0.12], [0, 0.08], [1000, 0.12]],
+  alarm: [[960, 0.25], [640, 0.25], [960, 0.25], [640, 0.25], [960, 0.25], [640, 0.25]],
+}
+
+export const toneNames = Object.keys(tones)
+
+let context: AudioContext | null = null
+
+export function playTone(name: string) {
+  const notes = tones[name]
+  if (!notes) return
+  context ??= new AudioContext()
+  let at = context.currentTime
+  for (const [frequency, duration] of notes) {
+    if (frequency > 0) {
+      const oscillator = context.createOscillator()
+      const gain = context.createGain()
+      oscillator.frequency.value = frequency
+      gain.gain.setValueAtTime(0.3, at)
+      gain.gain.exponentialRampToValueAtTime(0.001, at + duration)
+      oscillator.connect(gain).connect(context.destination)
+      oscillator.start(at)
+      oscillator.stop(at + duration)
+    }
+    at += duration
+  }
+}
+
+/** Plays the tone of every alert the Rust side raises. */
+export async function listenForAlerts() {
+  return await listen<Alert>('alert', (event) => {
+    if (event.payload.tone) playTone(event.payload.tone)
+  })
+}
@@ -0,0 +1,24 @@
+// Connection diagnostics kept by src-tauri/src/diagnostics.rs, sent as a
+// `diagnostics` event on every heartbeat and whenever the connection changes.
+import { invoke } from '@tauri-apps/api/core'
+
+export interface DiagnosticsReport {
+  status: { state: string, reason: string | null, retry_at: number | null } | null
+  /** Unix time in milliseconds */
+  connected_since: number | null
+  rtt_ms: number | null
+  rtt_avg_ms: number | null
+  connection_attempts: number
+  reconnects: number
+  /** Messages waiting to be written to the socket */
+  queued: number
+  pending_requests: number
+  messages_sent: number
+  messages_received: number
+  last_error: { message: string, at: number } | null
+}
+
+export const getDiagnostics = () => invoke<DiagnosticsReport>('get_diagnostics')
+
+/** Saves recent logs and state for support; resolves with the file's path. */
+export const saveDiagnosticsBundle = () => invoke<string>('save_diagnostics_bundle')
@@ -0,0 +1,136 @@
+// Typed wrappers around the dispatch commands in src-tauri/src/commands.rs.
+// Each resolves with the server's reply or rejects with a CommandError.
+// Commands that change calls or units resolve with a Delivery instead, as
+// they are queued in the outbox (src-tauri/src/outbox.rs) while offline.
+import { invoke } from '@tauri-apps/api/core'
+import type {
+  CallNature,
+  CallUpdate,
+  Event,
+  EventId,
+  IncidentCall,
+  IncidentId,
+  JsonValue,
+  ProtocolMessage,
+  Location,
+  LocationId,
+  LocationLink,
+  MapLayer,
+  NewEvent,
+  NewIncidentCall,
+  NewLocation,
+  NewLocationLink,
+  Position,
+  RollCall,
+  RollCallId,
+  Route,
+  Unit,
+  UnitId,
+  UnitRecommendation,
+  UnitStatus,
+} from './schema'
+
+export type CommandError =
+  | { kind: 'invalid-argument', message: string }
+  | { kind: 'not-connected' }
+  | { kind: 'disconnected' }
+  | { kind: 'timeout' }
+  | { kind: 'server', message: string }
+  | { kind: 'unexpected-reply', message: string }
+
+/** Either the server's reply, or the ID of the outbox item it was queued as. */
+export type Delivery<T> =
+  | { outcome: 'done', value: T }
+  | { outcome: 'queued', value: number }
+
+export type OutboxStatus = 'pending' | 'sent' | 'conflicted' | 'unknown'
+
+export interface OutboxItem {
+  id: number
+  /** Server the operation was made against; only replayed while it is active */
+  server_url: string
+  /** Unix time in milliseconds */
+  created_at: number
+  message: ProtocolMessage
+  status: OutboxStatus
+  error: string | null
+}
+
+const errorLabels: Record<CommandError['kind'], string> = {
+  'invalid-argument': 'Invalid input',
+  'not-connected': 'Not connected to the dispatch server',
+  'disconnected': 'Connection lost before the server replied',
+  'timeout': 'The server did not reply in time',
+  'server': 'The server refused the request',
+  'unexpected-reply': 'Unexpected reply from the server',
+}
+
+/** A message for the user describing a rejected command. */
+export function describeCommandError(error: unknown): string {
+  const e = error as Partial<CommandError>
+  if (!e?.kind) return String(error)
+  return 'message' in e && e.message ? e.message : errorLabels[e.kind]
+}
+
+export const dispatch = {
+  ping: () => invoke<void>('send_ping'),
+
+  listEvents: () => invoke<Event[]>('list_events'),
+  createEvent: (event: NewEvent) => invoke<Event>('create_event', { event }),
+  setActiveEvent: (eventId: EventId) => invoke<Event>('set_active_event', { eventId }),
+
+  getActiveCalls: () => invoke<IncidentCall[]>('get_active_calls'),
+  getEventCalls: (eventId: EventId) => invoke<IncidentCall[]>('get_event_calls', { eventId }),
+  /** `date` is a calendar day of the active event, e.g. `2024-11-03` */
+  getCallsOnDay: (date: string) => invoke<IncidentCall[]>('get_calls_on_day', { date }),
+  getCall: (id: IncidentId) => invoke<IncidentCall>('get_call', { id }),
+  createCall: (call: NewIncidentCall) => invoke<Delivery<IncidentCall>>('create_call', { call }),
+  updateCall: (id: IncidentId, update: Partial<CallUpdate>) =>
+    invoke<Delivery<IncidentCall>>('update_call', { id, update }),
+  addNote: (incidentId: IncidentId, content: string) =>
+    invoke<Delivery<IncidentCall>>('add_note', { incidentId, content }),
+  getCallNatures: () => invoke<CallNature[]>('get_call_natures'),
+  getPendingQueue: () => invoke<IncidentCall[]>('get_pending_queue'),
+  recommendUnits: (incidentId: IncidentId, accessibleOnly: boolean) =>
+    invoke<UnitRecommendation[]>('recommend_units', { incidentId, accessibleOnly }),
+  dispatchUnit: (incidentId: IncidentId, unitId: UnitId) =>
+    invoke<Delivery<void>>('dispatch_unit', { incidentId, unitId }),
+
+  getUnits: () => invoke<Unit[]>('get_units'),
+  updateUnitStatus: (unitId: UnitId, status: UnitStatus, location: string | null = null) =>
+    invoke<Delivery<void>>('update_unit_status', { unitId, status, location }),
+  checkInUnit: (unitId: UnitId) => invoke<Delivery<void>>('check_in_unit', { unitId }),
+  updateUnitLocation: (unitId: UnitId, locationId: LocationId) =>
+    invoke<Delivery<void>>('update_unit_location', { unitId, locationId }),
+  updateUnitPosition: (unitId: UnitId, position: Position) =>
+    invoke<void>('update_unit_position', { unitId, position }),
+
+  activateEmergency: (unitId: UnitId, location: string | null = null) =>
+    invoke<void>('activate_emergency', { unitId, location }),
+  acknowledgeEmergency: (unitId: UnitId) => invoke<void>('acknowledge_emergency', { unitId }),
+  clearEmergency: (unitId: UnitId) => invoke<void>('clear_emergency', { unitId }),
+
+  startRollCall: (reason: string) => invoke<void>('start_roll_call', { reason }),
+  markUnitAccounted: (rollCallId: RollCallId, unitId: UnitId) =>
+    invoke<Delivery<void>>('mark_unit_accounted', { rollCallId, unitId }),
+  realertRollCall: (rollCallId: RollCallId) => invoke<void>('realert_roll_call', { rollCallId }),
+  completeRollCall: (rollCallId: RollCallId) => invoke<void>('complete_roll_call', { rollCallId }),
+  getRollCalls: () => invoke<RollCall[]>('get_roll_calls'),
+
+  getLocations: () => invoke<Location[]>('get_locations'),
+  resolveLocation: (query: string) => invoke<Location[]>('resolve_location', { query }),
+  createLocation: (location: NewLocation) => invoke<Location>('create_location', { location }),
+  setLocationPosition: (locationId: LocationId, position: Position) =>
+    invoke<Location>('set_location_position', { locationId, position }),
+  importMapLayer: (name: string, geojson: JsonValue) =>
+    invoke<number>('import_map_layer', { name, geojson }),
+  getMapLayers: () => invoke<MapLayer[]>('get_map_layers'),
+  createLocationLink: (link: NewLocationLink) => invoke<LocationLink>('create_location_link', { link }),
+  getLocationLinks: () => invoke<LocationLink[]>('get_location_links'),
+  getRoute: (fromLocationId: LocationId, toLocationId: LocationId, accessibleOnly: boolean) =>
+    invoke<Route | null>('get_route', { fromLocationId, toLocationId, accessibleOnly }),
+
+  listOutbox: () => invoke<OutboxItem[]>('list_outbox'),
+  /** Pending items can't be discarded */
+  discardOutboxItem: (id: number) => invoke<void>('discard_outbox_item', { id }),
+}
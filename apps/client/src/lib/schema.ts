@@ -1,3 +1,6 @@
+// Protocol types generated from libs/shared-types (see its codegen module)
+export type * from '../../../../libs/shared-types/bindings/protocol'
+
 export interface Response<T> {
   success: boolean
   data?: T
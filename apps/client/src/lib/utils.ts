@@ -4,3 +4,8 @@ import { twMerge } from 'tailwind-merge'
 export function cn(...inputs: ClassValue[]) {
   return twMerge(clsx(inputs))
 }
+
+/** Renders a wire timestamp as the event's clocks read it, whatever the zone of this machine. */
+export function formatEventTime(at: string, timeZone: string | undefined) {
+  return new Date(at).toLocaleTimeString(undefined, { timeZone, timeZoneName: 'short' })
+}
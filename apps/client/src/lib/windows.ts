@@ -0,0 +1,18 @@
+// Pop-out windows managed by src-tauri/src/windows.rs. Each shows one route of
+// this app and follows the same Rust-side state as the main window.
+import { invoke } from '@tauri-apps/api/core'
+import { getCurrentWindow } from '@tauri-apps/api/window'
+import type { IncidentId } from './schema'
+
+export type PopOut =
+  | { panel: 'incident', id: IncidentId }
+  | { panel: 'unit-board' }
+
+export const openPopOut = (popOut: PopOut) => invoke<void>('open_pop_out', { popOut })
+
+export const popOutIncident = (id: IncidentId) => openPopOut({ panel: 'incident', id })
+
+export const popOutUnitBoard = () => openPopOut({ panel: 'unit-board' })
+
+/** Whether this is the main console window rather than a pop-out. */
+export const isMainWindow = () => getCurrentWindow().label === 'main'
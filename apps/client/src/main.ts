@@ -6,6 +6,10 @@ import router from './router'
 import i18n from './i18n'
 import App from './App.vue'
 import { useSettingsStore } from '@/stores/settings'
+import { useDispatchStore } from '@/stores/dispatchStore'
+import { useOutboxStore } from '@/stores/outboxStore'
+import { listenForAlerts } from '@/lib/alerts'
+import { isMainWindow } from '@/lib/windows'
 import { invoke } from "@tauri-apps/api/core"
 import '@fortawesome/fontawesome-free/css/all.css';
 
@@ -18,6 +22,13 @@ app.use(router)
 app.use(i18n)
 
 initAppSettings()
+// Follow the calls and units the Rust side keeps in step with the server
+useDispatchStore().start()
+// Follow operations queued while offline
+useOutboxStore().start()
+// Sound the tones of alerts raised for new calls, emergencies and assignments,
+// once for the whole app rather than in every pop-out
+if (isMainWindow()) listenForAlerts()
 
 app.mount('#app')
 
@@ -1,11 +1,16 @@
 import { createRouter, createWebHistory, RouteRecordRaw } from 'vue-router';
 import Settings from '@/pages/Settings.vue';
 import DispatchMain from '@/pages/DispatchMain.vue';
+import IncidentPopOut from '@/pages/IncidentPopOut.vue';
+import UnitBoard from '@/pages/UnitBoard.vue';
 
 const routes: Array<RouteRecordRaw> = [
   { path: '/', name: 'dashboard', component: DispatchMain },
   { path: '/settings', name: 'Settings', component: Settings },
   { path: '/dispatch', name: 'DispatchMain', component: DispatchMain },
+  // Pop-out windows, shown without the main window's menus and panels
+  { path: '/popout/incident/:id', name: 'IncidentPopOut', component: IncidentPopOut, props: true, meta: { popOut: true } },
+  { path: '/popout/units', name: 'UnitBoard', component: UnitBoard, meta: { popOut: true } },
 ];
 
 const router = createRouter({
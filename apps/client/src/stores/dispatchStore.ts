@@ -0,0 +1,109 @@
+import { defineStore } from 'pinia'
+import { invoke } from '@tauri-apps/api/core'
+import { listen, type UnlistenFn } from '@tauri-apps/api/event'
+import type { Event, IncidentCall, IncidentId, OnlineDispatcher, Unit } from '@/lib/schema'
+
+// Mirrors the Rust-side local state (src-tauri/src/local_state.rs). The Rust
+// side is authoritative; this store only follows its state-change events.
+// While offline it holds the last known state, read-only, with `staleSince`
+// set to when it was last in step with the server.
+interface Snapshot {
+  event: Event | null
+  calls: IncidentCall[]
+  units: Unit[]
+  presence: OnlineDispatcher[]
+  stale_since: number | null
+}
+
+type StateChange =
+  | { type: 'active_event_changed', payload: { event: Event } }
+  | { type: 'calls_replaced', payload: { calls: IncidentCall[] } }
+  | { type: 'call_updated', payload: { call: IncidentCall } }
+  | { type: 'call_removed', payload: { id: IncidentId } }
+  | { type: 'units_replaced', payload: { units: Unit[] } }
+  | { type: 'unit_updated', payload: { unit: Unit } }
+  | { type: 'presence_changed', payload: { online: OnlineDispatcher[] } }
+  | { type: 'stale_changed', payload: { since: number | null } }
+  | { type: 'replaced', payload: { snapshot: Snapshot } }
+
+const priorityOrder = { high: 0, medium: 1, low: 2 }
+
+function sortCalls(calls: IncidentCall[]) {
+  return calls.sort((a, b) =>
+    priorityOrder[a.priority] - priorityOrder[b.priority]
+    || Date.parse(a.times.received) - Date.parse(b.times.received))
+}
+
+export const useDispatchStore = defineStore('dispatch', {
+  state: () => ({
+    event: null as Event | null,
+    calls: [] as IncidentCall[],
+    units: [] as Unit[],
+    /** Dispatchers with a console on the event */
+    presence: [] as OnlineDispatcher[],
+    /** Unix time in milliseconds, while the state is out of date */
+    staleSince: null as number | null,
+    unlisten: null as UnlistenFn | null,
+  }),
+  getters: {
+    readOnly: (state) => state.staleSince !== null,
+  },
+  actions: {
+    async start() {
+      if (this.unlisten) return
+      // Listen first so no change slips between the snapshot and the listener
+      this.unlisten = await listen<StateChange>('state-change', (event) => this.apply(event.payload))
+      this.load(await invoke<Snapshot>('get_local_state'))
+    },
+    async stop() {
+      this.unlisten?.()
+      this.unlisten = null
+    },
+    load(snapshot: Snapshot) {
+      this.event = snapshot.event
+      this.calls = snapshot.calls
+      this.units = snapshot.units
+      this.presence = snapshot.presence
+      this.staleSince = snapshot.stale_since
+    },
+    apply(change: StateChange) {
+      switch (change.type) {
+        case 'active_event_changed':
+          this.event = change.payload.event
+          this.calls = []
+          this.units = []
+          this.presence = []
+          break
+        case 'calls_replaced':
+          this.calls = change.payload.calls
+          break
+        case 'call_updated': {
+          const call = change.payload.call
+          this.calls = sortCalls([...this.calls.filter(c => c.id !== call.id), call])
+          break
+        }
+        case 'call_removed':
+          this.calls = this.calls.filter(c => c.id !== change.payload.id)
+          break
+        case 'units_replaced':
+          this.units = change.payload.units
+          break
+        case 'unit_updated': {
+          const unit = change.payload.unit
+          this.units = [...this.units.filter(u => u.id !== unit.id), unit]
+            .sort((a, b) => a.name.localeCompare(b.name))
+          break
+        }
+        case 'presence_changed':
+          this.presence = change.payload.online
+          break
+        case 'stale_changed':
+          this.staleSince = change.payload.since
+          break
+        case 'replaced':
+          this.load(change.payload.snapshot)
+          break
+      }
+    },
+  },
+})
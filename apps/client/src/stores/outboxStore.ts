@@ -0,0 +1,32 @@
+import { defineStore } from 'pinia'
+import { listen, type UnlistenFn } from '@tauri-apps/api/event'
+import { dispatch, type OutboxItem } from '@/lib/dispatch'
+
+// Operations waiting in the Rust-side outbox (src-tauri/src/outbox.rs), which
+// sends the whole list in an `outbox-changed` event whenever it changes.
+export const useOutboxStore = defineStore('outbox', {
+  state: () => ({
+    items: [] as OutboxItem[],
+    unlisten: null as UnlistenFn | null,
+  }),
+  getters: {
+    pending: (state) => state.items.filter(item => item.status === 'pending'),
+    conflicted: (state) => state.items.filter(item => item.status === 'conflicted'),
+  },
+  actions: {
+    async start() {
+      if (this.unlisten) return
+      this.unlisten = await listen<OutboxItem[]>('outbox-changed', (event) => {
+        this.items = event.payload
+      })
+      this.items = await dispatch.listOutbox()
+    },
+    async stop() {
+      this.unlisten?.()
+      this.unlisten = null
+    },
+    async discard(id: number) {
+      await dispatch.discardOutboxItem(id)
+    },
+  },
+})
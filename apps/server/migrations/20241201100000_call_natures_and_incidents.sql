@@ -0,0 +1,59 @@
+-- Enum types shared with the protocol crate
+CREATE TYPE incident_type AS ENUM ('security', 'medical');
+CREATE TYPE priority AS ENUM ('high', 'medium', 'low');
+CREATE TYPE unit_type AS ENUM ('security', 'first_aid');
+CREATE TYPE disposition AS ENUM ('resolved', 'unresolved', 'pending');
+
+-- Nature / chief complaint code table
+CREATE TABLE call_natures (
+    code TEXT PRIMARY KEY,
+    description TEXT NOT NULL,
+    default_priority priority NOT NULL,
+    default_incident_type incident_type NOT NULL,
+    recommended_unit_types unit_type[] NOT NULL DEFAULT '{}',
+    is_active BOOLEAN NOT NULL DEFAULT TRUE
+);
+
+INSERT INTO call_natures (code, description, default_priority, default_incident_type, recommended_unit_types) VALUES
+    ('CHEST', 'Chest pain', 'high', 'medical', '{first_aid}'),
+    ('BREATH', 'Difficulty breathing', 'high', 'medical', '{first_aid}'),
+    ('UNCON', 'Unconscious / unresponsive person', 'high', 'medical', '{first_aid,security}'),
+    ('FALL', 'Fall / injury', 'medium', 'medical', '{first_aid}'),
+    ('HEAT', 'Heat exhaustion / dehydration', 'medium', 'medical', '{first_aid}'),
+    ('SICK', 'Sick person', 'low', 'medical', '{first_aid}'),
+    ('INTOX', 'Intoxicated person', 'medium', 'medical', '{first_aid,security}'),
+    ('FIGHT', 'Fight / assault', 'high', 'security', '{security,first_aid}'),
+    ('DIST', 'Disturbance', 'medium', 'security', '{security}'),
+    ('HARASS', 'Harassment', 'medium', 'security', '{security}'),
+    ('THEFT', 'Theft report', 'low', 'security', '{security}'),
+    ('LOST', 'Lost person / child', 'high', 'security', '{security}'),
+    ('SUSP', 'Suspicious person / package', 'medium', 'security', '{security}');
+
+-- Incidents
+CREATE SEQUENCE incident_number_seq;
+
+CREATE TABLE incidents (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    incident_number TEXT UNIQUE NOT NULL
+        DEFAULT TO_CHAR(NOW(), 'YYYY') || '-' || LPAD(nextval('incident_number_seq')::TEXT, 5, '0'),
+    event TEXT NOT NULL,
+    date_of_service TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    name TEXT NOT NULL,
+    location TEXT NOT NULL,
+    dob TIMESTAMPTZ,
+    badge_number TEXT,
+    phone_number TEXT NOT NULL,
+    caller_name TEXT NOT NULL,
+    incident_type incident_type NOT NULL,
+    nature_code TEXT NOT NULL REFERENCES call_natures(code),
+    disposition disposition NOT NULL DEFAULT 'pending',
+    received_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    assigned_at TIMESTAMPTZ,
+    responding_at TIMESTAMPTZ,
+    on_scene_at TIMESTAMPTZ,
+    transporting_at TIMESTAMPTZ,
+    at_destination_at TIMESTAMPTZ,
+    cleared_at TIMESTAMPTZ
+);
+
+CREATE INDEX IF NOT EXISTS idx_incidents_disposition ON incidents(disposition);
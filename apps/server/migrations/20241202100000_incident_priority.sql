@@ -0,0 +1,7 @@
+ALTER TABLE incidents ADD COLUMN priority priority NOT NULL DEFAULT 'medium';
+ALTER TABLE incidents ADD COLUMN escalated_at TIMESTAMPTZ;
+
+-- Pending queue lookups: unassigned, uncleared calls by priority and age
+CREATE INDEX IF NOT EXISTS idx_incidents_pending
+    ON incidents(priority, received_at)
+    WHERE assigned_at IS NULL AND cleared_at IS NULL;
@@ -0,0 +1,15 @@
+CREATE TYPE unit_status AS ENUM ('available', 'dispatched', 'on_scene', 'unavailable');
+
+CREATE TABLE units (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    name TEXT UNIQUE NOT NULL,
+    unit_type unit_type NOT NULL,
+    status unit_status NOT NULL DEFAULT 'unavailable',
+    status_since TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    last_check_in TIMESTAMPTZ,
+    -- Set when an overdue alert is raised; cleared by a status change or check-in
+    overdue_alerted_at TIMESTAMPTZ
+);
+
+ALTER TYPE action_type ADD VALUE IF NOT EXISTS 'unit_status_change';
+ALTER TYPE action_type ADD VALUE IF NOT EXISTS 'unit_check_in';
@@ -0,0 +1,12 @@
+ALTER TABLE units ADD COLUMN last_known_location TEXT;
+ALTER TABLE units ADD COLUMN emergency_since TIMESTAMPTZ;
+ALTER TABLE units ADD COLUMN emergency_incident_id UUID REFERENCES incidents(id);
+ALTER TABLE units ADD COLUMN emergency_acknowledged_by UUID REFERENCES users(id);
+ALTER TABLE units ADD COLUMN emergency_acknowledged_at TIMESTAMPTZ;
+
+INSERT INTO call_natures (code, description, default_priority, default_incident_type, recommended_unit_types) VALUES
+    ('EMERG', 'Unit emergency / needs help', 'high', 'security', '{security,first_aid}');
+
+ALTER TYPE action_type ADD VALUE IF NOT EXISTS 'emergency_activated';
+ALTER TYPE action_type ADD VALUE IF NOT EXISTS 'emergency_acknowledged';
+ALTER TYPE action_type ADD VALUE IF NOT EXISTS 'emergency_cleared';
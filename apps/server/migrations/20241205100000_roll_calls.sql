@@ -0,0 +1,24 @@
+CREATE TABLE roll_calls (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    event TEXT NOT NULL,
+    reason TEXT NOT NULL,
+    started_by UUID NOT NULL REFERENCES users(id),
+    started_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    completed_by UUID REFERENCES users(id),
+    completed_at TIMESTAMPTZ
+);
+
+-- Unit names are copied so the archive survives units being renamed or removed
+CREATE TABLE roll_call_entries (
+    roll_call_id UUID NOT NULL REFERENCES roll_calls(id) ON DELETE CASCADE,
+    unit_id UUID NOT NULL REFERENCES units(id),
+    unit_name TEXT NOT NULL,
+    accounted_at TIMESTAMPTZ,
+    accounted_by UUID REFERENCES users(id),
+    PRIMARY KEY (roll_call_id, unit_id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_roll_calls_event ON roll_calls(event);
+
+ALTER TYPE action_type ADD VALUE IF NOT EXISTS 'roll_call_started';
+ALTER TYPE action_type ADD VALUE IF NOT EXISTS 'roll_call_completed';
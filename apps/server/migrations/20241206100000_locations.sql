@@ -0,0 +1,21 @@
+CREATE TYPE location_kind AS ENUM ('building', 'floor', 'room', 'outdoor_zone');
+
+CREATE TABLE locations (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    event TEXT NOT NULL,
+    name TEXT NOT NULL,
+    kind location_kind NOT NULL,
+    parent_id UUID REFERENCES locations(id),
+    aliases TEXT[] NOT NULL DEFAULT '{}',
+    -- Normalized name and aliases (lowercase, alphanumerics only) used for resolution
+    search_keys TEXT[] NOT NULL DEFAULT '{}',
+    UNIQUE (event, name)
+);
+
+CREATE INDEX IF NOT EXISTS idx_locations_event ON locations(event);
+CREATE INDEX IF NOT EXISTS idx_locations_search_keys ON locations USING GIN (search_keys);
+
+-- Incidents now point at a registered location; the old free text becomes detail
+ALTER TABLE incidents RENAME COLUMN location TO location_detail;
+ALTER TABLE incidents ALTER COLUMN location_detail DROP NOT NULL;
+ALTER TABLE incidents ADD COLUMN location_id UUID REFERENCES locations(id);
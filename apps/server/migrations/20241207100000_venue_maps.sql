@@ -0,0 +1,19 @@
+ALTER TYPE location_kind ADD VALUE IF NOT EXISTS 'post';
+
+ALTER TABLE locations ADD COLUMN longitude DOUBLE PRECISION;
+ALTER TABLE locations ADD COLUMN latitude DOUBLE PRECISION;
+
+ALTER TABLE units ADD COLUMN last_longitude DOUBLE PRECISION;
+ALTER TABLE units ADD COLUMN last_latitude DOUBLE PRECISION;
+ALTER TABLE units ADD COLUMN last_position_at TIMESTAMPTZ;
+
+-- Imported floorplans / site maps, stored as GeoJSON FeatureCollections
+CREATE TABLE map_layers (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    event TEXT NOT NULL,
+    name TEXT NOT NULL,
+    geojson JSONB NOT NULL,
+    imported_by UUID NOT NULL REFERENCES users(id),
+    imported_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    UNIQUE (event, name)
+);
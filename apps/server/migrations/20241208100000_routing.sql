@@ -0,0 +1,28 @@
+CREATE TYPE link_kind AS ENUM ('hallway', 'elevator', 'stairs', 'escalator', 'outdoor');
+
+-- Walkable graph between registered locations
+CREATE TABLE location_links (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    event TEXT NOT NULL,
+    from_location_id UUID NOT NULL REFERENCES locations(id) ON DELETE CASCADE,
+    to_location_id UUID NOT NULL REFERENCES locations(id) ON DELETE CASCADE,
+    kind link_kind NOT NULL,
+    travel_secs INTEGER NOT NULL CHECK (travel_secs >= 0),
+    accessible BOOLEAN NOT NULL DEFAULT TRUE,
+    bidirectional BOOLEAN NOT NULL DEFAULT TRUE
+);
+
+CREATE INDEX IF NOT EXISTS idx_location_links_event ON location_links(event);
+
+ALTER TABLE units ADD COLUMN location_id UUID REFERENCES locations(id);
+
+-- Units dispatched to incidents
+CREATE TABLE incident_units (
+    incident_id UUID NOT NULL REFERENCES incidents(id) ON DELETE CASCADE,
+    unit_id UUID NOT NULL REFERENCES units(id),
+    assigned_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    eta_secs INTEGER,
+    PRIMARY KEY (incident_id, unit_id)
+);
+
+ALTER TYPE action_type ADD VALUE IF NOT EXISTS 'dispatch_unit';
@@ -0,0 +1,66 @@
+CREATE TABLE events (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    name TEXT UNIQUE NOT NULL,
+    starts_on DATE NOT NULL,
+    ends_on DATE NOT NULL,
+    time_zone TEXT NOT NULL DEFAULT 'UTC',
+    settings JSONB NOT NULL DEFAULT '{}',
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    CHECK (ends_on >= starts_on)
+);
+
+-- Carry over every free-text event name already in use
+INSERT INTO events (name, starts_on, ends_on)
+SELECT event, MIN(seen)::DATE, MAX(seen)::DATE
+FROM (
+    SELECT event, received_at AS seen FROM incidents
+    UNION ALL SELECT event, started_at FROM roll_calls
+    UNION ALL SELECT event, imported_at FROM map_layers
+    UNION ALL SELECT event, NOW() FROM locations
+    UNION ALL SELECT event, NOW() FROM location_links
+) existing
+GROUP BY event;
+
+-- Units were never scoped; give them somewhere to live if nothing else exists
+INSERT INTO events (name, starts_on, ends_on)
+SELECT 'Default', CURRENT_DATE, CURRENT_DATE
+WHERE EXISTS (SELECT 1 FROM units) AND NOT EXISTS (SELECT 1 FROM events);
+
+ALTER TABLE incidents ADD COLUMN event_id UUID REFERENCES events(id);
+UPDATE incidents t SET event_id = e.id FROM events e WHERE e.name = t.event;
+ALTER TABLE incidents ALTER COLUMN event_id SET NOT NULL;
+ALTER TABLE incidents DROP COLUMN event;
+CREATE INDEX IF NOT EXISTS idx_incidents_event ON incidents(event_id);
+
+ALTER TABLE roll_calls ADD COLUMN event_id UUID REFERENCES events(id);
+UPDATE roll_calls t SET event_id = e.id FROM events e WHERE e.name = t.event;
+ALTER TABLE roll_calls ALTER COLUMN event_id SET NOT NULL;
+ALTER TABLE roll_calls DROP COLUMN event;
+CREATE INDEX IF NOT EXISTS idx_roll_calls_event_id ON roll_calls(event_id);
+
+ALTER TABLE map_layers ADD COLUMN event_id UUID REFERENCES events(id);
+UPDATE map_layers t SET event_id = e.id FROM events e WHERE e.name = t.event;
+ALTER TABLE map_layers ALTER COLUMN event_id SET NOT NULL;
+ALTER TABLE map_layers DROP COLUMN event;
+ALTER TABLE map_layers ADD UNIQUE (event_id, name);
+
+ALTER TABLE locations ADD COLUMN event_id UUID REFERENCES events(id);
+UPDATE locations t SET event_id = e.id FROM events e WHERE e.name = t.event;
+ALTER TABLE locations ALTER COLUMN event_id SET NOT NULL;
+ALTER TABLE locations DROP COLUMN event;
+ALTER TABLE locations ADD UNIQUE (event_id, name);
+CREATE INDEX IF NOT EXISTS idx_locations_event_id ON locations(event_id);
+
+ALTER TABLE location_links ADD COLUMN event_id UUID REFERENCES events(id);
+UPDATE location_links t SET event_id = e.id FROM events e WHERE e.name = t.event;
+ALTER TABLE location_links ALTER COLUMN event_id SET NOT NULL;
+ALTER TABLE location_links DROP COLUMN event;
+CREATE INDEX IF NOT EXISTS idx_location_links_event_id ON location_links(event_id);
+
+ALTER TABLE units ADD COLUMN event_id UUID REFERENCES events(id);
+UPDATE units SET event_id = (SELECT id FROM events ORDER BY starts_on DESC LIMIT 1);
+ALTER TABLE units ALTER COLUMN event_id SET NOT NULL;
+ALTER TABLE units DROP CONSTRAINT IF EXISTS units_name_key;
+ALTER TABLE units ADD UNIQUE (event_id, name);
+
+ALTER TABLE action_logs ADD COLUMN event_id UUID REFERENCES events(id);
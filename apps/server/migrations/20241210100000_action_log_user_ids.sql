@@ -0,0 +1,7 @@
+-- Action logs were written with the user id as text; store it as the UUID it is.
+-- Anything that isn't a UUID can't name a user, so it is dropped.
+ALTER TABLE action_logs ALTER COLUMN user_id TYPE UUID USING (
+    CASE WHEN user_id ~* '^[0-9a-f]{8}-[0-9a-f]{4}-[0-9a-f]{4}-[0-9a-f]{4}-[0-9a-f]{12}$'
+        THEN user_id::UUID
+    END
+);
@@ -0,0 +1,13 @@
+-- Narrative notes dispatchers add to a call
+CREATE TABLE incident_notes (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    incident_id UUID NOT NULL REFERENCES incidents(id) ON DELETE CASCADE,
+    author_id UUID NOT NULL REFERENCES users(id),
+    content TEXT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_incident_notes_incident ON incident_notes(incident_id, created_at);
+
+ALTER TYPE action_type ADD VALUE IF NOT EXISTS 'update_call';
+ALTER TYPE action_type ADD VALUE IF NOT EXISTS 'add_note';
@@ -0,0 +1,177 @@
+use shared_types::accountability::{RollCall, RollCallEntry};
+use shared_types::id::{EventId, RollCallId, UnitId, UserId};
+use sqlx::types::{time::OffsetDateTime, Uuid};
+
+use crate::AppState;
+
+struct RollCallRow {
+    id: Uuid,
+    event_id: Uuid,
+    reason: String,
+    started_by: String,
+    started_at: OffsetDateTime,
+    completed_at: Option<OffsetDateTime>,
+}
+
+impl AppState {
+    /// Starts a roll call covering every unit that is not `Unavailable`.
+    pub async fn start_roll_call(&self, event_id: EventId, reason: &str, started_by: UserId) -> Result<RollCall, sqlx::Error> {
+        let mut tx = self.db.begin().await?;
+
+        let id = sqlx::query_scalar!(
+            r#"
+            INSERT INTO roll_calls (event_id, reason, started_by)
+            VALUES ($1, $2, $3)
+            RETURNING id as "id: RollCallId"
+            "#,
+            event_id as EventId,
+            reason,
+            started_by as UserId,
+        )
+        .fetch_one(&mut *tx)
+        .await?;
+
+        sqlx::query!(
+            r#"
+            INSERT INTO roll_call_entries (roll_call_id, unit_id, unit_name)
+            SELECT $1, id, name FROM units
+            WHERE event_id = $2 AND status <> 'unavailable'
+            "#,
+            id as RollCallId,
+            event_id as EventId,
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+
+        self.get_roll_call(id)
+            .await?
+            .ok_or(sqlx::Error::RowNotFound)
+    }
+
+    pub async fn get_roll_call(&self, id: RollCallId) -> Result<Option<RollCall>, sqlx::Error> {
+        let row = sqlx::query_as!(
+            RollCallRow,
+            r#"
+            SELECT r.id, r.event_id, r.reason, u.email as started_by, r.started_at, r.completed_at
+            FROM roll_calls r
+            JOIN users u ON u.id = r.started_by
+            WHERE r.id = $1
+            "#,
+            id as RollCallId,
+        )
+        .fetch_optional(&self.db)
+        .await?;
+
+        match row {
+            Some(row) => Ok(Some(self.roll_call_with_entries(row).await?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Archived and in-progress roll calls for an event, newest first.
+    pub async fn list_roll_calls(&self, event_id: EventId) -> Result<Vec<RollCall>, sqlx::Error> {
+        let rows = sqlx::query_as!(
+            RollCallRow,
+            r#"
+            SELECT r.id, r.event_id, r.reason, u.email as started_by, r.started_at, r.completed_at
+            FROM roll_calls r
+            JOIN users u ON u.id = r.started_by
+            WHERE r.event_id = $1
+            ORDER BY r.started_at DESC
+            "#,
+            event_id as EventId,
+        )
+        .fetch_all(&self.db)
+        .await?;
+
+        let mut roll_calls = Vec::with_capacity(rows.len());
+        for row in rows {
+            roll_calls.push(self.roll_call_with_entries(row).await?);
+        }
+        Ok(roll_calls)
+    }
+
+    async fn roll_call_with_entries(&self, row: RollCallRow) -> Result<RollCall, sqlx::Error> {
+        let entries = sqlx::query!(
+            r#"
+            SELECT e.unit_id, e.unit_name, e.accounted_at, u.email as "accounted_by?"
+            FROM roll_call_entries e
+            LEFT JOIN users u ON u.id = e.accounted_by
+            WHERE e.roll_call_id = $1
+            ORDER BY e.unit_name
+            "#,
+            row.id,
+        )
+        .fetch_all(&self.db)
+        .await?
+        .into_iter()
+        .map(|entry| RollCallEntry {
+            unit_id: entry.unit_id.into(),
+            unit_name: entry.unit_name,
+            accounted_at: entry.accounted_at,
+            accounted_by: entry.accounted_by,
+        })
+        .collect();
+
+        Ok(RollCall {
+            id: row.id.into(),
+            event_id: row.event_id.into(),
+            reason: row.reason,
+            started_by: row.started_by,
+            started_at: row.started_at,
+            completed_at: row.completed_at,
+            entries,
+        })
+    }
+
+    /// Marks a unit as accounted for. Returns `false` if the unit is not part of
+    /// the roll call or the roll call has already completed.
+    pub async fn mark_unit_accounted(
+        &self,
+        event_id: EventId,
+        roll_call_id: RollCallId,
+        unit_id: UnitId,
+        user_id: UserId,
+    ) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query!(
+            r#"
+            UPDATE roll_call_entries e
+            SET accounted_at = COALESCE(e.accounted_at, NOW()),
+                accounted_by = COALESCE(e.accounted_by, $3)
+            FROM roll_calls r
+            WHERE r.id = e.roll_call_id
+                AND e.roll_call_id = $1 AND e.unit_id = $2
+                AND r.event_id = $4 AND r.completed_at IS NULL
+            "#,
+            roll_call_id as RollCallId,
+            unit_id as UnitId,
+            user_id as UserId,
+            event_id as EventId,
+        )
+        .execute(&self.db)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Closes a roll call, archiving it with its event. Returns `false` if it was
+    /// already completed or does not exist.
+    pub async fn complete_roll_call(&self, event_id: EventId, roll_call_id: RollCallId, user_id: UserId) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query!(
+            r#"
+            UPDATE roll_calls
+            SET completed_at = NOW(), completed_by = $2
+            WHERE id = $1 AND event_id = $3 AND completed_at IS NULL
+            "#,
+            roll_call_id as RollCallId,
+            user_id as UserId,
+            event_id as EventId,
+        )
+        .execute(&self.db)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+}
@@ -1,6 +1,6 @@
 use std::sync::Arc;
 use axum::{
-    extract::Request, middleware::Next, response::Response, Extension
+    extract::{Request, State}, middleware::Next, response::Response
 };
 use axum_extra::{headers::{authorization::Bearer, Authorization}, TypedHeader};
 
@@ -69,6 +69,15 @@ impl CloudflareAuth {
         })
     }
 
+    /// Trusts tokens signed by `public_key` without asking Cloudflare.
+    #[cfg(test)]
+    pub fn with_public_key(public_key: &str) -> Self {
+        Self {
+            config: Arc::new(CloudflareConfig::new("test".to_string(), "test".to_string())),
+            public_key: Arc::new(public_key.to_string()),
+        }
+    }
+
     pub async fn validate_token(&self, token: &str) -> Result<CloudflareJWTClaims, jsonwebtoken::errors::Error> {
         let validation = Validation::new(jsonwebtoken::Algorithm::RS256);
         let key = DecodingKey::from_rsa_pem(self.public_key.as_bytes())?;
@@ -84,12 +93,16 @@ impl CloudflareAuth {
 }
 
 pub async fn cloudflare_auth_middleware(
-    TypedHeader(auth): TypedHeader<Authorization<Bearer>>,
-    state: Extension<Arc<AppState>>,
+    auth: Option<TypedHeader<Authorization<Bearer>>>,
+    State(state): State<Arc<AppState>>,
     mut request: Request<axum::body::Body>,
     next: Next,
 ) -> Result<Response, (axum::http::StatusCode, String)> {
-    // Extract and validate the token
+    // Extract and validate the token; a missing token is a 401 like a bad one,
+    // so clients can tell it apart from a malformed request
+    let Some(TypedHeader(auth)) = auth else {
+        return Err((axum::http::StatusCode::UNAUTHORIZED, "Missing bearer token".to_string()));
+    };
     let token = auth.token();
     let claims = state.cf_auth.validate_token(token).await
         .map_err(|e| (axum::http::StatusCode::UNAUTHORIZED, format!("Invalid token: {}", e)))?;
@@ -1,4 +1,5 @@
 use sqlx::{postgres::PgPoolOptions, Pool, Postgres};
+use shared_types::id::{EventId, UserId};
 use crate::logging::ActionLog;
 
 pub type DbPool = Pool<Postgres>;
@@ -18,14 +19,15 @@ pub async fn save_action_log(pool: &DbPool, log: &ActionLog) -> Result<(), sqlx:
     sqlx::query!(
         r#"
         INSERT INTO action_logs 
-        (timestamp, action_type, user_id, ip_address, details)
-        VALUES ($1, $2, $3, $4, $5)
+        (timestamp, action_type, user_id, ip_address, details, event_id)
+        VALUES ($1, $2, $3, $4, $5, $6)
         "#,
         log.timestamp,
         log.action_type as _,
-        log.user_id,
+        log.user_id as Option<UserId>,
         log.ip_address.map(|ip| ip.to_string()),
-        log.details
+        log.details,
+        log.event_id as Option<EventId>,
     )
     .execute(pool)
     .await?;
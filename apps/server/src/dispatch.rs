@@ -0,0 +1,74 @@
+use shared_types::id::{EventId, IncidentId, UnitId};
+use shared_types::incident::{IncidentCall, UnitStatus};
+use shared_types::routing::UnitRecommendation;
+
+use crate::AppState;
+
+impl AppState {
+    /// Available units of the incident nature's recommended types, ordered by
+    /// walking ETA to the incident. Units with no known route come last.
+    pub async fn recommend_units(
+        &self,
+        event_id: EventId,
+        incident: &IncidentCall,
+        accessible_only: bool,
+    ) -> Result<Vec<UnitRecommendation>, sqlx::Error> {
+        let recommended_types = &incident.call_nature.recommended_unit_types;
+        let units = self
+            .list_units(event_id)
+            .await?
+            .into_iter()
+            .filter(|unit| unit.status == UnitStatus::Available)
+            .filter(|unit| recommended_types.is_empty() || recommended_types.contains(&unit.unit_type));
+
+        let routes = match incident.location.location_id {
+            Some(target) => Some(self.walk_graph(event_id, accessible_only).await?.routes_to(target)),
+            None => None,
+        };
+
+        let mut recommendations: Vec<UnitRecommendation> = units
+            .map(|unit| {
+                let route = routes.as_ref().and_then(|routes| {
+                    routes.route_from(unit.location_id?)
+                });
+                UnitRecommendation { unit, route }
+            })
+            .collect();
+        recommendations.sort_by_key(|r| r.route.as_ref().map_or(u32::MAX, |route| route.eta_secs));
+
+        Ok(recommendations)
+    }
+
+    /// Dispatches a unit to an incident, recording its walking ETA from its
+    /// current location. Returns the updated incident, or `None` if either the
+    /// incident or the unit does not exist in the event.
+    pub async fn dispatch_unit(
+        &self,
+        event_id: EventId,
+        incident_id: IncidentId,
+        unit_id: UnitId,
+    ) -> Result<Option<IncidentCall>, sqlx::Error> {
+        let Some(incident) = self.get_incident(incident_id).await? else {
+            return Ok(None);
+        };
+        if incident.event_id != event_id {
+            return Ok(None);
+        }
+        let Some(unit) = self.get_unit(event_id, unit_id).await? else {
+            return Ok(None);
+        };
+
+        let eta_secs = match (unit.location_id, incident.location.location_id) {
+            (Some(from), Some(to)) => self
+                .walk_graph(event_id, false)
+                .await?
+                .routes_to(to)
+                .route_from(from)
+                .map(|route| route.eta_secs),
+            _ => None,
+        };
+
+        self.assign_unit(incident_id, unit_id, eta_secs).await?;
+        self.get_incident(incident_id).await
+    }
+}
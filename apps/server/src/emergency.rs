@@ -0,0 +1,122 @@
+use shared_types::id::{EventId, IncidentId, UnitId, UserId};
+
+use crate::AppState;
+
+/// Outcome of a unit activating its emergency.
+pub struct EmergencyActivation {
+    pub incident_id: IncidentId,
+    pub location: String,
+}
+
+impl AppState {
+    /// Flags the unit as in emergency and opens a linked high-priority incident at
+    /// its last known location. Re-activating an active emergency returns the
+    /// existing incident rather than opening another.
+    pub async fn activate_emergency(
+        &self,
+        event_id: EventId,
+        unit_id: UnitId,
+        location: Option<&str>,
+    ) -> Result<Option<EmergencyActivation>, sqlx::Error> {
+        let mut tx = self.db.begin().await?;
+
+        let Some(unit) = sqlx::query!(
+            r#"
+            UPDATE units
+            SET last_known_location = COALESCE($2, last_known_location),
+                emergency_since = COALESCE(emergency_since, NOW())
+            WHERE id = $1 AND event_id = $3
+            RETURNING name, last_known_location, location_id, emergency_incident_id
+            "#,
+            unit_id as UnitId,
+            location,
+            event_id as EventId,
+        )
+        .fetch_optional(&mut *tx)
+        .await?
+        else {
+            return Ok(None);
+        };
+
+        let location = unit.last_known_location.unwrap_or_else(|| "Unknown".to_string());
+
+        if let Some(incident_id) = unit.emergency_incident_id {
+            tx.commit().await?;
+            return Ok(Some(EmergencyActivation {
+                incident_id: incident_id.into(),
+                location,
+            }));
+        }
+
+        let incident_id = sqlx::query_scalar!(
+            r#"
+            INSERT INTO incidents
+            (event_id, name, location_id, location_detail, phone_number, caller_name, incident_type, priority, nature_code)
+            SELECT $4, $1, $3, $2, '', $1, default_incident_type, default_priority, code
+            FROM call_natures
+            WHERE code = 'EMERG'
+            RETURNING id as "id: IncidentId"
+            "#,
+            unit.name,
+            location,
+            unit.location_id,
+            event_id as EventId,
+        )
+        .fetch_one(&mut *tx)
+        .await?;
+
+        sqlx::query!(
+            r#"
+            UPDATE units
+            SET emergency_incident_id = $2
+            WHERE id = $1
+            "#,
+            unit_id as UnitId,
+            incident_id as IncidentId,
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+        Ok(Some(EmergencyActivation { incident_id, location }))
+    }
+
+    /// Records a supervisor's acknowledgement. Returns `false` if the unit has no
+    /// active emergency.
+    pub async fn acknowledge_emergency(&self, event_id: EventId, unit_id: UnitId, supervisor_id: UserId) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query!(
+            r#"
+            UPDATE units
+            SET emergency_acknowledged_by = $2, emergency_acknowledged_at = NOW()
+            WHERE id = $1 AND event_id = $3 AND emergency_since IS NOT NULL
+            "#,
+            unit_id as UnitId,
+            supervisor_id as UserId,
+            event_id as EventId,
+        )
+        .execute(&self.db)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Clears an acknowledged emergency. Returns `false` if the unit has no
+    /// emergency or it has not been acknowledged yet.
+    pub async fn clear_emergency(&self, event_id: EventId, unit_id: UnitId) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query!(
+            r#"
+            UPDATE units
+            SET emergency_since = NULL, emergency_incident_id = NULL,
+                emergency_acknowledged_by = NULL, emergency_acknowledged_at = NULL
+            WHERE id = $1 AND event_id = $2
+                AND emergency_since IS NOT NULL AND emergency_acknowledged_at IS NOT NULL
+            "#,
+            unit_id as UnitId,
+            event_id as EventId,
+        )
+        .execute(&self.db)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+}
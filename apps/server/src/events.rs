@@ -0,0 +1,98 @@
+use shared_types::event::{Event, NewEvent};
+use shared_types::id::EventId;
+use sqlx::types::{
+    time::{Date, OffsetDateTime},
+    Uuid,
+};
+
+use crate::AppState;
+
+struct EventRow {
+    id: Uuid,
+    name: String,
+    starts_on: Date,
+    ends_on: Date,
+    time_zone: String,
+    settings: serde_json::Value,
+}
+
+impl From<EventRow> for Event {
+    fn from(row: EventRow) -> Self {
+        Event {
+            id: row.id.into(),
+            name: row.name,
+            starts_on: row.starts_on,
+            ends_on: row.ends_on,
+            time_zone: row.time_zone,
+            settings: row.settings,
+        }
+    }
+}
+
+impl AppState {
+    /// Every event, most recent first.
+    pub async fn list_events(&self) -> Result<Vec<Event>, sqlx::Error> {
+        let events = sqlx::query_as!(
+            EventRow,
+            r#"
+            SELECT id, name, starts_on, ends_on, time_zone, settings
+            FROM events
+            ORDER BY starts_on DESC, name
+            "#,
+        )
+        .fetch_all(&self.db)
+        .await?;
+
+        Ok(events.into_iter().map(Event::from).collect())
+    }
+
+    pub async fn get_event(&self, id: EventId) -> Result<Option<Event>, sqlx::Error> {
+        let event = sqlx::query_as!(
+            EventRow,
+            r#"
+            SELECT id, name, starts_on, ends_on, time_zone, settings
+            FROM events
+            WHERE id = $1
+            "#,
+            id as EventId,
+        )
+        .fetch_optional(&self.db)
+        .await?;
+
+        Ok(event.map(Event::from))
+    }
+
+    pub async fn create_event(&self, event: &NewEvent) -> Result<Event, sqlx::Error> {
+        let event = sqlx::query_as!(
+            EventRow,
+            r#"
+            INSERT INTO events (name, starts_on, ends_on, time_zone, settings)
+            VALUES ($1, $2, $3, $4, $5)
+            RETURNING id, name, starts_on, ends_on, time_zone, settings
+            "#,
+            event.name,
+            event.starts_on,
+            event.ends_on,
+            event.time_zone,
+            event.settings,
+        )
+        .fetch_one(&self.db)
+        .await?;
+
+        Ok(event.into())
+    }
+
+    /// The event running now in its own time zone, used as a console's active
+    /// event until it picks one. If events overlap, the one that started most
+    /// recently wins.
+    pub async fn current_event(&self) -> Result<Option<EventId>, sqlx::Error> {
+        let now = OffsetDateTime::now_utc();
+        let current = self
+            .list_events()
+            .await?
+            .into_iter()
+            .find(|event| event.is_running_at(now));
+
+        Ok(current.map(|event| event.id))
+    }
+}
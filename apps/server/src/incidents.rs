@@ -0,0 +1,340 @@
+use shared_types::id::{EventId, IncidentId, LocationId, NoteId, UserId};
+use shared_types::incident::{
+    CallNature, CallUpdate, Disposition, IncidentCall, IncidentTimes, IncidentType, NewIncidentCall, Note,
+    Priority, UnitType,
+};
+use shared_types::location::IncidentLocation;
+use sqlx::types::{time::OffsetDateTime, Uuid};
+
+use crate::AppState;
+
+/// `query_as!` into an [`IncidentRow`]: the select list every incident query
+/// shares, followed by that query's own `WHERE`/`ORDER BY`.
+macro_rules! select_incidents {
+    ($rest:literal, $($args:tt)*) => {
+        sqlx::query_as!(
+            IncidentRow,
+            r#"
+            SELECT i.id, i.incident_number, i.event_id, i.date_of_service, i.name,
+                i.location_id, l.name as "location_name?", i.location_detail,
+                i.dob, i.badge_number, i.phone_number, i.caller_name,
+                i.incident_type as "incident_type: IncidentType",
+                i.priority as "priority: Priority",
+                i.disposition as "disposition: Disposition",
+                n.code as nature_code,
+                n.description as nature_description,
+                n.default_priority as "nature_default_priority: Priority",
+                n.default_incident_type as "nature_default_incident_type: IncidentType",
+                n.recommended_unit_types as "nature_recommended_unit_types: Vec<UnitType>",
+                i.received_at, i.assigned_at, i.responding_at, i.on_scene_at,
+                i.transporting_at, i.at_destination_at, i.cleared_at, i.escalated_at
+            FROM incidents i
+            JOIN call_natures n ON n.code = i.nature_code
+            LEFT JOIN locations l ON l.id = i.location_id
+            "# + $rest,
+            $($args)*
+        )
+    };
+}
+
+/// Flat row shape of `incidents` joined with its call nature.
+struct IncidentRow {
+    id: Uuid,
+    incident_number: String,
+    event_id: Uuid,
+    date_of_service: OffsetDateTime,
+    name: String,
+    location_id: Option<Uuid>,
+    location_name: Option<String>,
+    location_detail: Option<String>,
+    dob: Option<OffsetDateTime>,
+    badge_number: Option<String>,
+    phone_number: String,
+    caller_name: String,
+    incident_type: IncidentType,
+    priority: Priority,
+    disposition: Disposition,
+    nature_code: String,
+    nature_description: String,
+    nature_default_priority: Priority,
+    nature_default_incident_type: IncidentType,
+    nature_recommended_unit_types: Vec<UnitType>,
+    received_at: OffsetDateTime,
+    assigned_at: Option<OffsetDateTime>,
+    responding_at: Option<OffsetDateTime>,
+    on_scene_at: Option<OffsetDateTime>,
+    transporting_at: Option<OffsetDateTime>,
+    at_destination_at: Option<OffsetDateTime>,
+    cleared_at: Option<OffsetDateTime>,
+    escalated_at: Option<OffsetDateTime>,
+}
+
+impl From<IncidentRow> for IncidentCall {
+    fn from(row: IncidentRow) -> Self {
+        IncidentCall {
+            id: row.id.into(),
+            incident_number: row.incident_number,
+            event_id: row.event_id.into(),
+            date_of_service: row.date_of_service,
+            name: row.name,
+            location: IncidentLocation {
+                location_id: row.location_id.map(Into::into),
+                name: row.location_name,
+                detail: row.location_detail,
+            },
+            dob: row.dob,
+            badge_number: row.badge_number,
+            phone_number: row.phone_number,
+            caller_name: row.caller_name,
+            incident_type: row.incident_type,
+            priority: row.priority,
+            call_nature: CallNature {
+                code: row.nature_code,
+                description: row.nature_description,
+                default_priority: row.nature_default_priority,
+                default_incident_type: row.nature_default_incident_type,
+                recommended_unit_types: row.nature_recommended_unit_types,
+            },
+            notes: Vec::new(),
+            disposition: row.disposition,
+            units_assigned: Vec::new(),
+            times: IncidentTimes {
+                received: row.received_at,
+                assigned: row.assigned_at,
+                responding: row.responding_at,
+                on_scene: row.on_scene_at,
+                transporting: row.transporting_at,
+                at_destination: row.at_destination_at,
+                cleared: row.cleared_at,
+                escalated: row.escalated_at,
+            },
+        }
+    }
+}
+
+impl AppState {
+    /// Opens a new incident, taking any unset fields from the nature's defaults.
+    /// Returns `None` if the call's location is not one of the event's.
+    pub async fn create_incident(
+        &self,
+        event_id: EventId,
+        call: &NewIncidentCall,
+        nature: &CallNature,
+    ) -> Result<Option<IncidentCall>, sqlx::Error> {
+        let incident_type = call
+            .incident_type
+            .clone()
+            .unwrap_or_else(|| nature.default_incident_type.clone());
+        let priority = call.priority.unwrap_or(nature.default_priority);
+
+        let id = sqlx::query_scalar!(
+            r#"
+            INSERT INTO incidents
+            (event_id, name, location_id, location_detail, dob, badge_number, phone_number, caller_name,
+                incident_type, priority, nature_code)
+            SELECT $1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11
+            WHERE $3::uuid IS NULL OR EXISTS (SELECT 1 FROM locations WHERE id = $3 AND event_id = $1)
+            RETURNING id as "id: IncidentId"
+            "#,
+            event_id as EventId,
+            call.name,
+            call.location_id as Option<LocationId>,
+            call.location_detail,
+            call.dob,
+            call.badge_number,
+            call.phone_number,
+            call.caller_name,
+            incident_type as IncidentType,
+            priority as Priority,
+            nature.code,
+        )
+        .fetch_optional(&self.db)
+        .await?;
+
+        match id {
+            Some(id) => self.get_incident(id).await,
+            None => Ok(None),
+        }
+    }
+
+    pub async fn get_incident(&self, id: IncidentId) -> Result<Option<IncidentCall>, sqlx::Error> {
+        let row = select_incidents!(
+            "WHERE i.id = $1",
+            id as IncidentId,
+        )
+        .fetch_optional(&self.db)
+        .await?;
+
+        let Some(row) = row else {
+            return Ok(None);
+        };
+        let mut call = IncidentCall::from(row);
+        call.units_assigned = self.units_for_incident(id).await?;
+        call.notes = self.notes_for_incident(id).await?;
+        Ok(Some(call))
+    }
+
+    /// Changes the fields set in `update` on a call of the event. Returns `None`
+    /// if there is no such call in the event, or the new location is not one of
+    /// the event's.
+    pub async fn update_incident(
+        &self,
+        event_id: EventId,
+        id: IncidentId,
+        update: &CallUpdate,
+    ) -> Result<Option<IncidentCall>, sqlx::Error> {
+        let updated = sqlx::query!(
+            r#"
+            UPDATE incidents
+            SET name = COALESCE($3, name),
+                location_id = CASE WHEN $11 THEN $4 ELSE location_id END,
+                location_detail = CASE WHEN $12 THEN $5 ELSE location_detail END,
+                phone_number = COALESCE($6, phone_number),
+                caller_name = COALESCE($7, caller_name),
+                incident_type = COALESCE($8, incident_type),
+                priority = COALESCE($9, priority),
+                -- A new priority has its own threshold to wait past
+                escalated_at = CASE WHEN $9 <> priority THEN NULL ELSE escalated_at END,
+                disposition = COALESCE($10, disposition)
+            WHERE id = $1 AND event_id = $2
+                AND ($4::uuid IS NULL OR EXISTS (SELECT 1 FROM locations WHERE id = $4 AND event_id = $2))
+            "#,
+            id as IncidentId,
+            event_id as EventId,
+            update.name,
+            update.location_id.flatten() as Option<LocationId>,
+            update.location_detail.clone().flatten(),
+            update.phone_number,
+            update.caller_name,
+            update.incident_type.clone() as Option<IncidentType>,
+            update.priority as Option<Priority>,
+            update.disposition.clone() as Option<Disposition>,
+            update.location_id.is_some(),
+            update.location_detail.is_some(),
+        )
+        .execute(&self.db)
+        .await?;
+
+        if updated.rows_affected() == 0 {
+            return Ok(None);
+        }
+        self.get_incident(id).await
+    }
+
+    /// Adds a note to a call of the event. Returns `None` if there is no such
+    /// call in the event.
+    pub async fn add_note(
+        &self,
+        event_id: EventId,
+        incident_id: IncidentId,
+        author_id: UserId,
+        content: &str,
+    ) -> Result<Option<IncidentCall>, sqlx::Error> {
+        let added = sqlx::query!(
+            r#"
+            INSERT INTO incident_notes (incident_id, author_id, content)
+            SELECT id, $3, $4
+            FROM incidents
+            WHERE id = $1 AND event_id = $2
+            "#,
+            incident_id as IncidentId,
+            event_id as EventId,
+            author_id as UserId,
+            content,
+        )
+        .execute(&self.db)
+        .await?;
+
+        if added.rows_affected() == 0 {
+            return Ok(None);
+        }
+        self.get_incident(incident_id).await
+    }
+
+    /// A call's notes, oldest first.
+    async fn notes_for_incident(&self, incident_id: IncidentId) -> Result<Vec<Note>, sqlx::Error> {
+        sqlx::query_as!(
+            Note,
+            r#"
+            SELECT n.id as "id: NoteId", u.email as author, n.content, n.created_at as timestamp
+            FROM incident_notes n
+            JOIN users u ON u.id = n.author_id
+            WHERE n.incident_id = $1
+            ORDER BY n.created_at
+            "#,
+            incident_id as IncidentId,
+        )
+        .fetch_all(&self.db)
+        .await
+    }
+
+    /// Calls that have not yet had a unit assigned, highest priority and longest waiting first.
+    pub async fn pending_queue(&self, event_id: EventId) -> Result<Vec<IncidentCall>, sqlx::Error> {
+        let rows = select_incidents!(
+            "WHERE i.event_id = $1 AND i.assigned_at IS NULL AND i.cleared_at IS NULL
+            ORDER BY i.priority, i.received_at",
+            event_id as EventId,
+        )
+        .fetch_all(&self.db)
+        .await?;
+
+        self.with_assigned_units(rows).await
+    }
+
+    /// Calls in an event that have not been cleared, highest priority first.
+    pub async fn active_calls(&self, event_id: EventId) -> Result<Vec<IncidentCall>, sqlx::Error> {
+        let rows = select_incidents!(
+            "WHERE i.event_id = $1 AND i.cleared_at IS NULL
+            ORDER BY i.priority, i.received_at",
+            event_id as EventId,
+        )
+        .fetch_all(&self.db)
+        .await?;
+
+        self.with_assigned_units(rows).await
+    }
+
+    /// Every call in an event, oldest first, for review and reporting.
+    pub async fn event_calls(&self, event_id: EventId) -> Result<Vec<IncidentCall>, sqlx::Error> {
+        let rows = select_incidents!(
+            "WHERE i.event_id = $1
+            ORDER BY i.received_at",
+            event_id as EventId,
+        )
+        .fetch_all(&self.db)
+        .await?;
+
+        self.with_assigned_units(rows).await
+    }
+
+    /// Calls received in `[from, until)`, oldest first.
+    pub async fn calls_received_between(
+        &self,
+        event_id: EventId,
+        from: OffsetDateTime,
+        until: OffsetDateTime,
+    ) -> Result<Vec<IncidentCall>, sqlx::Error> {
+        let rows = select_incidents!(
+            "WHERE i.event_id = $1 AND i.received_at >= $2 AND i.received_at < $3
+            ORDER BY i.received_at",
+            event_id as EventId,
+            from,
+            until,
+        )
+        .fetch_all(&self.db)
+        .await?;
+
+        self.with_assigned_units(rows).await
+    }
+
+    async fn with_assigned_units(&self, rows: Vec<IncidentRow>) -> Result<Vec<IncidentCall>, sqlx::Error> {
+        let mut calls = Vec::with_capacity(rows.len());
+        for row in rows {
+            let mut call = IncidentCall::from(row);
+            call.units_assigned = self.units_for_incident(call.id).await?;
+            call.notes = self.notes_for_incident(call.id).await?;
+            calls.push(call);
+        }
+        Ok(calls)
+    }
+}
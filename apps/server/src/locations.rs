@@ -0,0 +1,159 @@
+use shared_types::id::{EventId, LocationId};
+use shared_types::location::{Location, LocationKind, NewLocation};
+use shared_types::map::Position;
+use sqlx::types::Uuid;
+
+use crate::map::position;
+use crate::AppState;
+
+/// Maximum number of suggestions returned when resolving a typed location.
+const MAX_MATCHES: i64 = 10;
+
+/// Reduces a location name to the form used for matching, so that "Ballroom B",
+/// "ballroom b" and "BALLROOM-B" compare equal.
+pub fn normalize_location_name(name: &str) -> String {
+    name.chars()
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+struct LocationRow {
+    id: Uuid,
+    event_id: Uuid,
+    name: String,
+    kind: LocationKind,
+    parent_id: Option<Uuid>,
+    aliases: Vec<String>,
+    longitude: Option<f64>,
+    latitude: Option<f64>,
+}
+
+impl From<LocationRow> for Location {
+    fn from(row: LocationRow) -> Self {
+        Location {
+            id: row.id.into(),
+            event_id: row.event_id.into(),
+            name: row.name,
+            kind: row.kind,
+            parent_id: row.parent_id.map(Into::into),
+            aliases: row.aliases,
+            position: position(row.longitude, row.latitude),
+        }
+    }
+}
+
+impl AppState {
+    pub async fn list_locations(&self, event_id: EventId) -> Result<Vec<Location>, sqlx::Error> {
+        let locations = sqlx::query_as!(
+            LocationRow,
+            r#"
+            SELECT id, event_id, name, kind as "kind: LocationKind", parent_id, aliases,
+                longitude, latitude
+            FROM locations
+            WHERE event_id = $1
+            ORDER BY name
+            "#,
+            event_id as EventId,
+        )
+        .fetch_all(&self.db)
+        .await?;
+
+        Ok(locations.into_iter().map(Location::from).collect())
+    }
+
+    /// Registers a location. Returns `None` if its parent is not one of the
+    /// event's locations.
+    pub async fn create_location(
+        &self,
+        event_id: EventId,
+        location: &NewLocation,
+    ) -> Result<Option<Location>, sqlx::Error> {
+        let search_keys: Vec<String> = std::iter::once(&location.name)
+            .chain(&location.aliases)
+            .map(|name| normalize_location_name(name))
+            .filter(|key| !key.is_empty())
+            .collect();
+
+        let location = sqlx::query_as!(
+            LocationRow,
+            r#"
+            INSERT INTO locations (event_id, name, kind, parent_id, aliases, search_keys)
+            SELECT $1, $2, $3, $4, $5, $6
+            WHERE $4::uuid IS NULL OR EXISTS (SELECT 1 FROM locations WHERE id = $4 AND event_id = $1)
+            RETURNING id, event_id, name, kind as "kind: LocationKind", parent_id, aliases,
+                longitude, latitude
+            "#,
+            event_id as EventId,
+            location.name,
+            location.kind as LocationKind,
+            location.parent_id as Option<LocationId>,
+            &location.aliases,
+            &search_keys,
+        )
+        .fetch_optional(&self.db)
+        .await?;
+
+        Ok(location.map(Location::from))
+    }
+
+    /// Suggests registered locations for free text: exact name/alias matches first,
+    /// then prefix matches, then anything containing the query.
+    pub async fn resolve_location(&self, event_id: EventId, query: &str) -> Result<Vec<Location>, sqlx::Error> {
+        let key = normalize_location_name(query);
+        if key.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let locations = sqlx::query_as!(
+            LocationRow,
+            r#"
+            SELECT id, event_id, name, kind as "kind: LocationKind", parent_id, aliases,
+                longitude, latitude
+            FROM locations
+            WHERE event_id = $1
+                AND EXISTS (SELECT 1 FROM unnest(search_keys) k WHERE strpos(k, $2) > 0)
+            ORDER BY
+                CASE
+                    WHEN $2 = ANY(search_keys) THEN 0
+                    WHEN EXISTS (SELECT 1 FROM unnest(search_keys) k WHERE starts_with(k, $2)) THEN 1
+                    ELSE 2
+                END,
+                name
+            LIMIT $3
+            "#,
+            event_id as EventId,
+            key,
+            MAX_MATCHES,
+        )
+        .fetch_all(&self.db)
+        .await?;
+
+        Ok(locations.into_iter().map(Location::from).collect())
+    }
+
+    pub async fn set_location_position(
+        &self,
+        event_id: EventId,
+        location_id: LocationId,
+        position: Position,
+    ) -> Result<Option<Location>, sqlx::Error> {
+        let location = sqlx::query_as!(
+            LocationRow,
+            r#"
+            UPDATE locations
+            SET longitude = $2, latitude = $3
+            WHERE id = $1 AND event_id = $4
+            RETURNING id, event_id, name, kind as "kind: LocationKind", parent_id, aliases, longitude, latitude
+            "#,
+            location_id as LocationId,
+            position.longitude,
+            position.latitude,
+            event_id as EventId,
+        )
+        .fetch_optional(&self.db)
+        .await?;
+
+        Ok(location.map(Location::from))
+    }
+}
@@ -1,4 +1,5 @@
 use serde::{Deserialize, Serialize};
+use shared_types::id::{EventId, UserId};
 use time::OffsetDateTime;
 use std::net::SocketAddr;
 
@@ -7,7 +8,9 @@ use std::net::SocketAddr;
 pub struct ActionLog {
     pub timestamp: OffsetDateTime,
     pub action_type: ActionType,
-    pub user_id: Option<String>,
+    pub user_id: Option<UserId>,
+    /// Active event of the console that took the action, if any
+    pub event_id: Option<EventId>,
     pub ip_address: Option<SocketAddr>,
     pub details: String,
 }
@@ -21,4 +24,13 @@ pub enum ActionType {
     UpdateCall,
     DeleteCall,
     OpenCall,
+    UnitStatusChange,
+    UnitCheckIn,
+    EmergencyActivated,
+    EmergencyAcknowledged,
+    EmergencyCleared,
+    RollCallStarted,
+    RollCallCompleted,
+    DispatchUnit,
+    AddNote,
 }
\ No newline at end of file
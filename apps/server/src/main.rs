@@ -3,10 +3,12 @@ use std::{net::SocketAddr, ops::ControlFlow, sync::Arc, time::Duration};
 use axum::{
     extract::{ws::
         {Message, WebSocket}, ConnectInfo, State, WebSocketUpgrade
-    }, middleware::from_fn_with_state, response::IntoResponse, routing::get, Router
+    }, middleware::from_fn_with_state, response::IntoResponse, routing::get, Extension, Router
 };
 use axum_extra::{headers, TypedHeader};
 use futures_util::{SinkExt, StreamExt};
+use shared_types::encoding::{Encoding, Frame};
+use shared_types::{Envelope, ProtocolMessage};
 use tower_http::trace::{DefaultMakeSpan, TraceLayer};
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 
@@ -14,8 +16,27 @@ mod db;
 mod logging;
 mod auth;
 mod users;
+mod events;
+mod natures;
+mod incidents;
+mod protocol;
+mod queue;
+mod units;
+mod unit_timers;
+mod emergency;
+mod accountability;
+mod locations;
+mod map;
+mod routing;
+mod dispatch;
+mod presence;
 use auth::{cloudflare_auth_middleware, CloudflareAuth};
 use db::{create_pool, DbPool};
+use presence::Presence;
+use protocol::{Broadcast, Session};
+use queue::QueueConfig;
+use unit_timers::StatusTimerConfig;
+use users::User;
 
 use axum::middleware;
 
@@ -26,6 +47,12 @@ use axum::middleware;
 pub struct AppState {
     pub db: DbPool,
     pub cf_auth: Arc<CloudflareAuth>,
+    /// Server-originated messages fanned out to connected consoles
+    pub broadcasts: tokio::sync::broadcast::Sender<Broadcast>,
+    /// Consoles connected to each event
+    pub presence: Arc<Presence>,
+    pub queue_config: QueueConfig,
+    pub unit_timer_config: StatusTimerConfig,
 }
 
 impl AppState {
@@ -37,7 +64,15 @@ impl AppState {
             dotenvy::var("CLOUDFLARE_AUD").unwrap(),
         );
         let cf_auth = Arc::new(CloudflareAuth::new(cloudflare_config).await.unwrap());
-        Self { db: db_pool, cf_auth }
+        let (broadcasts, _) = tokio::sync::broadcast::channel(256);
+        Self {
+            db: db_pool,
+            cf_auth,
+            broadcasts,
+            presence: Arc::default(),
+            queue_config: QueueConfig::from_env(),
+            unit_timer_config: StatusTimerConfig::from_env(),
+        }
     }
 }
 
@@ -59,28 +94,36 @@ async fn main() {
     // Create shared state
     let state = Arc::new(AppState::new().await);
 
-    // Setup router
-    let app = Router::new()
-        .route("/ws", get(ws_handler))
-        .layer(from_fn_with_state(state.clone(), cloudflare_auth_middleware))
-        .layer(
-            TraceLayer::new_for_http()
-                .make_span_with(DefaultMakeSpan::default().include_headers(true))
-        )
-        .with_state(state);
+    // Watch the pending queue for calls waiting too long
+    tokio::spawn(queue::run_escalation(state.clone()));
+    // Alert dispatchers to units overdue for a welfare check
+    tokio::spawn(unit_timers::run_status_timers(state.clone()));
 
     // Start server
     let listener = tokio::net::TcpListener::bind("0.0.0.0:3031").await.unwrap();
     tracing::debug!("Listening on: {}", listener.local_addr().unwrap());
     axum::serve(
         listener, 
-        app.into_make_service_with_connect_info::<SocketAddr>(),
+        router(state).into_make_service_with_connect_info::<SocketAddr>(),
     ).await.unwrap();
 }
 
+/// Every route sits behind the Cloudflare Access check.
+fn router(state: Arc<AppState>) -> Router {
+    Router::new()
+        .route("/ws", get(ws_handler))
+        .layer(from_fn_with_state(state.clone(), cloudflare_auth_middleware))
+        .layer(
+            TraceLayer::new_for_http()
+                .make_span_with(DefaultMakeSpan::default().include_headers(true))
+        )
+        .with_state(state)
+}
+
 async fn ws_handler(
     ws: WebSocketUpgrade,
-    State(_state): State<Arc<AppState>>,
+    State(state): State<Arc<AppState>>,
+    Extension(user): Extension<User>,
     user_agent: Option<TypedHeader<headers::UserAgent>>,
     ConnectInfo(addr): ConnectInfo<SocketAddr>,
 ) -> impl IntoResponse {
@@ -92,13 +135,31 @@ async fn ws_handler(
 
     
 
-    ws.on_upgrade(move |socket| handle_socket(socket, addr))
+    ws.on_upgrade(move |socket| handle_socket(socket, addr, state, user))
 }
 
-async fn handle_socket(socket: WebSocket, addr: SocketAddr) {
+fn to_message(frame: Frame) -> Message {
+    match frame {
+        Frame::Text(text) => Message::Text(text),
+        Frame::Binary(data) => Message::Binary(data),
+    }
+}
+
+async fn handle_socket(socket: WebSocket, addr: SocketAddr, state: Arc<AppState>, user: User) {
     let (mut sender, mut receiver) = socket.split();
     
-    let (ping_tx, mut ping_rx) = tokio::sync::mpsc::channel(32);
+    let (tx, mut rx) = tokio::sync::mpsc::channel(32);
+    let ping_tx = tx.clone();
+
+    // Start on whichever event is running today; the console can switch later
+    let active_event = match state.current_event().await {
+        Ok(event_id) => event_id,
+        Err(e) => {
+            tracing::error!("Failed to look up current event for {}: {}", addr, e);
+            None
+        }
+    };
+    let mut session = Session::new(user, active_event);
 
     // Send initial ping
     if sender.send(Message::Ping(vec![1, 2, 3])).await.is_ok() {
@@ -119,9 +180,43 @@ async fn handle_socket(socket: WebSocket, addr: SocketAddr) {
         }
     });
 
-     // Spawn task to forward ping messages to the WebSocket
+    // Forward broadcasts for this client's active event
+    let events_tx = tx.clone();
+    let mut events_rx = state.broadcasts.subscribe();
+    let session_id = session.id;
+    let active_event_rx = session.watch_active_event();
+    let encoding_rx = session.watch_encoding();
+    let events_task = tokio::spawn(async move {
+        loop {
+            let event = match events_rx.recv().await {
+                Ok(Broadcast { event_id, message, origin }) => {
+                    if event_id.is_some() && event_id != *active_event_rx.borrow() {
+                        continue;
+                    }
+                    if origin == Some(session_id) {
+                        continue;
+                    }
+                    message
+                }
+                Err(tokio::sync::broadcast::error::RecvError::Lagged(skipped)) => {
+                    tracing::warn!("Client {} missed {} events", addr, skipped);
+                    continue;
+                }
+                Err(tokio::sync::broadcast::error::RecvError::Closed) => break,
+            };
+            let encoding = *encoding_rx.borrow();
+            let Ok(frame) = encoding.encode(&event) else {
+                continue;
+            };
+            if events_tx.send(to_message(frame)).await.is_err() {
+                break;
+            }
+        }
+    });
+
+     // Spawn task to forward pings and replies to the WebSocket
      let forward_task = tokio::spawn(async move {
-        while let Some(msg) = ping_rx.recv().await {
+        while let Some(msg) = rx.recv().await {
             if sender.send(msg).await.is_err() {
                 break;
             }
@@ -133,23 +228,73 @@ async fn handle_socket(socket: WebSocket, addr: SocketAddr) {
     while let Some(msg) = receiver.next().await {
         match msg {
             Ok(message) => {
-                match message {
+                let decoded = match message {
                     Message::Text(text) => {
                         tracing::debug!("Received text message from {}: {}", addr, text);
-                        // Process text message if needed
+                        Some(serde_json::from_str::<Envelope>(&text).map_err(|e| e.to_string()))
+                    }
+                    Message::Binary(data) => {
+                        tracing::debug!("Received {} bytes from {}", data.len(), addr);
+                        Some(session.encoding().decode_binary(&data).map_err(|e| e.to_string()))
                     }
                     Message::Ping(_payload) => {
                         tracing::debug!("Received ping from {}", addr);
                         // No need to manually respond - axum handles pings automatically
+                        None
                     }
                     Message::Pong(_) => {
                         tracing::debug!("Received pong from {}", addr);
+                        None
                     }
                     Message::Close(_) => {
                         tracing::info!("Client {} requested close", addr);
                         break;
                     }
-                    _ => {}
+                };
+                let Some(decoded) = decoded else {
+                    continue;
+                };
+
+                let (request_id, reply) = match decoded {
+                    Ok(Envelope { request_id, message }) => {
+                        let reply = protocol::handle_message(&state, &mut session, message).await;
+                        // Requests with an ID always get a reply, so the client can stop waiting
+                        (request_id, reply.or(request_id.map(|_| ProtocolMessage::Ack)))
+                    }
+                    Err(e) => (None, Some(ProtocolMessage::Error {
+                        message: format!("Malformed message: {}", e),
+                    })),
+                };
+                let rejected = matches!(reply, Some(ProtocolMessage::UnsupportedVersion { .. }));
+                if let Some(reply) = reply {
+                    let reply = Envelope::reply(request_id, reply);
+                    match session.encoding().encode(&reply) {
+                        Ok(frame) => {
+                            if tx.send(to_message(frame)).await.is_err() {
+                                break;
+                            }
+                        }
+                        Err(e) => tracing::error!("Failed to serialize reply to {}: {}", addr, e),
+                    }
+                    // Everything after the Welcome uses the negotiated encoding
+                    if let ProtocolMessage::Welcome { capabilities, .. } = &reply.message {
+                        session.set_encoding(Encoding::from_capabilities(capabilities));
+                        if let Some(event) = protocol::active_event(&state, &session).await {
+                            match session.encoding().encode(&Envelope::from(event)) {
+                                Ok(frame) => {
+                                    if tx.send(to_message(frame)).await.is_err() {
+                                        break;
+                                    }
+                                }
+                                Err(e) => tracing::error!("Failed to serialize active event for {}: {}", addr, e),
+                            }
+                        }
+                        state.update_presence(&session);
+                    }
+                }
+                if rejected {
+                    let _ = tx.send(Message::Close(None)).await;
+                    break;
                 }
             }
             Err(e) => {
@@ -159,9 +304,15 @@ async fn handle_socket(socket: WebSocket, addr: SocketAddr) {
         }
     }
 
-    // Clean up
+    // Clean up, giving queued replies (such as a version rejection) a moment to go out
+    state.leave_presence(&session);
     ping_task.abort();
-    forward_task.abort();
+    events_task.abort();
+    drop(tx);
+    let forward_abort = forward_task.abort_handle();
+    if tokio::time::timeout(Duration::from_secs(1), forward_task).await.is_err() {
+        forward_abort.abort();
+    }
     
     tracing::info!("Client {} disconnected", addr);
 }
@@ -190,4 +341,115 @@ fn process_message(msg: Message, addr: SocketAddr) -> ControlFlow<(), ()> {
             ControlFlow::Break(())
         }
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use jsonwebtoken::{encode, EncodingKey, Header};
+    use sqlx::PgPool;
+    use tokio_tungstenite::tungstenite::{self, client::IntoClientRequest};
+
+    const PRIVATE_KEY: &str = include_str!("../tests/fixtures/jwt_test_key.pem");
+    const PUBLIC_KEY: &str = include_str!("../tests/fixtures/jwt_test_key.pub.pem");
+
+    /// Serves the real router on a free port with a test signing key.
+    async fn serve(db: PgPool) -> SocketAddr {
+        let (broadcasts, _) = tokio::sync::broadcast::channel(16);
+        let state = Arc::new(AppState {
+            db,
+            cf_auth: Arc::new(CloudflareAuth::with_public_key(PUBLIC_KEY)),
+            broadcasts,
+            presence: Arc::default(),
+            queue_config: QueueConfig::from_env(),
+            unit_timer_config: StatusTimerConfig::from_env(),
+        });
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            axum::serve(listener, router(state).into_make_service_with_connect_info::<SocketAddr>())
+                .await
+                .unwrap();
+        });
+        addr
+    }
+
+    fn token() -> String {
+        let now = time::OffsetDateTime::now_utc().unix_timestamp();
+        let claims = serde_json::json!({
+            "sub": "test-user",
+            "email": "dispatcher@example.com",
+            "name": "Test Dispatcher",
+            "iat": now,
+            "exp": now + 300,
+        });
+        let key = EncodingKey::from_rsa_pem(PRIVATE_KEY.as_bytes()).unwrap();
+        encode(&Header::new(jsonwebtoken::Algorithm::RS256), &claims, &key).unwrap()
+    }
+
+    type Client = tokio_tungstenite::WebSocketStream<tokio_tungstenite::MaybeTlsStream<tokio::net::TcpStream>>;
+
+    async fn send(ws: &mut Client, envelope: Envelope) {
+        let text = serde_json::to_string(&envelope).unwrap();
+        ws.send(tungstenite::Message::Text(text)).await.unwrap();
+    }
+
+    async fn receive(ws: &mut Client) -> Envelope {
+        loop {
+            if let tungstenite::Message::Text(text) = ws.next().await.unwrap().unwrap() {
+                return serde_json::from_str(&text).unwrap();
+            }
+        }
+    }
+
+    /// Connects with a valid token and says hello.
+    async fn connect(addr: SocketAddr) -> (Client, Envelope) {
+        let mut request = format!("ws://{}/ws", addr).into_client_request().unwrap();
+        request
+            .headers_mut()
+            .insert("Authorization", format!("Bearer {}", token()).parse().unwrap());
+        let (mut ws, _) = tokio_tungstenite::connect_async(request).await.unwrap();
+
+        let hello = ProtocolMessage::Hello {
+            min_version: shared_types::PROTOCOL_VERSION,
+            max_version: shared_types::PROTOCOL_VERSION,
+            client: "test".to_string(),
+            capabilities: Vec::new(),
+        };
+        send(&mut ws, hello.into()).await;
+        let welcome = receive(&mut ws).await;
+        (ws, welcome)
+    }
+
+    #[sqlx::test]
+    async fn valid_token_reaches_the_websocket(db: PgPool) {
+        let (_ws, reply) = connect(serve(db).await).await;
+        assert!(matches!(reply.message, ProtocolMessage::Welcome { .. }), "{:?}", reply.message);
+    }
+
+    #[sqlx::test]
+    async fn unsupported_requests_get_an_error(db: PgPool) {
+        let (mut ws, _) = connect(serve(db).await).await;
+        send(&mut ws, Envelope::reply(Some(7), ProtocolMessage::Ack)).await;
+        let reply = receive(&mut ws).await;
+        assert_eq!(reply.request_id, Some(7));
+        assert!(
+            matches!(&reply.message, ProtocolMessage::Error { message } if message == "Unsupported request: ack"),
+            "{:?}",
+            reply.message
+        );
+    }
+
+    #[sqlx::test]
+    async fn missing_or_bad_token_is_refused(db: PgPool) {
+        let addr = serve(db).await;
+        let url = format!("ws://{}/ws", addr);
+
+        let refused = tokio_tungstenite::connect_async(url.as_str()).await.unwrap_err();
+        assert!(matches!(refused, tungstenite::Error::Http(r) if r.status() == 401));
+
+        let mut request = url.into_client_request().unwrap();
+        request.headers_mut().insert("Authorization", "Bearer not-a-token".parse().unwrap());
+        let refused = tokio_tungstenite::connect_async(request).await.unwrap_err();
+        assert!(matches!(refused, tungstenite::Error::Http(r) if r.status() == 401));
+    }
+}
@@ -0,0 +1,221 @@
+use serde_json::{json, Value};
+use shared_types::id::{EventId, LocationId, UserId};
+use shared_types::map::{MapLayer, MapLayerKind, Position};
+
+use crate::locations::normalize_location_name;
+use crate::AppState;
+
+/// Builds a position from nullable longitude/latitude columns.
+pub fn position(longitude: Option<f64>, latitude: Option<f64>) -> Option<Position> {
+    Some(Position {
+        longitude: longitude?,
+        latitude: latitude?,
+    })
+}
+
+/// Checks that `geojson` is a `FeatureCollection` whose features all have a geometry.
+pub fn validate_feature_collection(geojson: &Value) -> Result<(), String> {
+    if geojson.get("type").and_then(Value::as_str) != Some("FeatureCollection") {
+        return Err("GeoJSON must be a FeatureCollection".to_string());
+    }
+    let features = geojson
+        .get("features")
+        .and_then(Value::as_array)
+        .ok_or("FeatureCollection has no features array")?;
+    for (i, feature) in features.iter().enumerate() {
+        if feature.get("type").and_then(Value::as_str) != Some("Feature") {
+            return Err(format!("features[{}] is not a Feature", i));
+        }
+        if !feature.get("geometry").is_some_and(Value::is_object) {
+            return Err(format!("features[{}] has no geometry", i));
+        }
+    }
+    Ok(())
+}
+
+/// Point features that identify a registered location, either by a `location_id`
+/// property or by a `name` property matching the location's name or an alias.
+fn located_points(geojson: &Value) -> Vec<(Option<LocationId>, Option<String>, Position)> {
+    let Some(features) = geojson.get("features").and_then(Value::as_array) else {
+        return Vec::new();
+    };
+
+    features
+        .iter()
+        .filter_map(|feature| {
+            let geometry = feature.get("geometry")?;
+            if geometry.get("type").and_then(Value::as_str) != Some("Point") {
+                return None;
+            }
+            let coordinates = geometry.get("coordinates")?.as_array()?;
+            let position = Position {
+                longitude: coordinates.first()?.as_f64()?,
+                latitude: coordinates.get(1)?.as_f64()?,
+            };
+
+            let properties = feature.get("properties");
+            let id = properties
+                .and_then(|p| p.get("location_id"))
+                .and_then(Value::as_str)
+                .and_then(|id| id.parse().ok());
+            let key = properties
+                .and_then(|p| p.get("name"))
+                .and_then(Value::as_str)
+                .map(normalize_location_name);
+            (id.is_some() || key.is_some()).then_some((id, key, position))
+        })
+        .collect()
+}
+
+fn point_feature(position: Position, properties: Value) -> Value {
+    json!({
+        "type": "Feature",
+        "geometry": {
+            "type": "Point",
+            "coordinates": [position.longitude, position.latitude],
+        },
+        "properties": properties,
+    })
+}
+
+fn feature_collection(features: Vec<Value>) -> Value {
+    json!({
+        "type": "FeatureCollection",
+        "features": features,
+    })
+}
+
+impl AppState {
+    /// Stores (or replaces) a venue layer and copies point coordinates onto the
+    /// registered locations they name. Returns how many locations were positioned.
+    pub async fn import_map_layer(
+        &self,
+        event_id: EventId,
+        name: &str,
+        geojson: &Value,
+        imported_by: UserId,
+    ) -> Result<usize, sqlx::Error> {
+        let mut tx = self.db.begin().await?;
+
+        sqlx::query!(
+            r#"
+            INSERT INTO map_layers (event_id, name, geojson, imported_by)
+            VALUES ($1, $2, $3, $4)
+            ON CONFLICT (event_id, name) DO UPDATE
+            SET geojson = EXCLUDED.geojson, imported_by = EXCLUDED.imported_by, imported_at = NOW()
+            "#,
+            event_id as EventId,
+            name,
+            geojson,
+            imported_by as UserId,
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        let mut located = 0;
+        for (id, key, position) in located_points(geojson) {
+            let result = sqlx::query!(
+                r#"
+                UPDATE locations
+                SET longitude = $4, latitude = $5
+                WHERE event_id = $1 AND (id = $2 OR $3 = ANY(search_keys))
+                "#,
+                event_id as EventId,
+                id as Option<LocationId>,
+                key,
+                position.longitude,
+                position.latitude,
+            )
+            .execute(&mut *tx)
+            .await?;
+            located += result.rows_affected() as usize;
+        }
+
+        tx.commit().await?;
+        Ok(located)
+    }
+
+    /// Imported venue layers for an event followed by generated unit and incident layers.
+    pub async fn map_layers(&self, event_id: EventId) -> Result<Vec<MapLayer>, sqlx::Error> {
+        let mut layers: Vec<MapLayer> = sqlx::query!(
+            r#"
+            SELECT name, geojson
+            FROM map_layers
+            WHERE event_id = $1
+            ORDER BY name
+            "#,
+            event_id as EventId,
+        )
+        .fetch_all(&self.db)
+        .await?
+        .into_iter()
+        .map(|layer| MapLayer {
+            name: layer.name,
+            kind: MapLayerKind::Venue,
+            geojson: layer.geojson,
+        })
+        .collect();
+
+        let units = self
+            .list_units(event_id)
+            .await?
+            .into_iter()
+            .filter_map(|unit| {
+                let position = unit.last_position?;
+                Some(point_feature(
+                    position,
+                    json!({
+                        "id": unit.id,
+                        "name": unit.name,
+                        "status": unit.status,
+                        "emergency": unit.emergency_since.is_some(),
+                    }),
+                ))
+            })
+            .collect();
+        layers.push(MapLayer {
+            name: "Units".to_string(),
+            kind: MapLayerKind::Units,
+            geojson: feature_collection(units),
+        });
+
+        // Times are shown as the venue's clocks read them, not in UTC
+        let event = self.get_event(event_id).await?;
+        let incidents = sqlx::query!(
+            r#"
+            SELECT i.id, i.incident_number, i.priority::TEXT as "priority!", i.received_at,
+                l.longitude as "longitude!", l.latitude as "latitude!"
+            FROM incidents i
+            JOIN locations l ON l.id = i.location_id
+            WHERE i.event_id = $1 AND i.cleared_at IS NULL
+                AND l.longitude IS NOT NULL AND l.latitude IS NOT NULL
+            "#,
+            event_id as EventId,
+        )
+        .fetch_all(&self.db)
+        .await?
+        .into_iter()
+        .map(|incident| {
+            point_feature(
+                Position {
+                    longitude: incident.longitude,
+                    latitude: incident.latitude,
+                },
+                json!({
+                    "id": incident.id.to_string(),
+                    "incident_number": incident.incident_number,
+                    "priority": incident.priority,
+                    "received": event.as_ref().map(|event| event.format_local(incident.received_at)),
+                }),
+            )
+        })
+        .collect();
+        layers.push(MapLayer {
+            name: "Incidents".to_string(),
+            kind: MapLayerKind::Incidents,
+            geojson: feature_collection(incidents),
+        });
+
+        Ok(layers)
+    }
+}
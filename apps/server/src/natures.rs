@@ -0,0 +1,43 @@
+use shared_types::incident::{CallNature, IncidentType, Priority, UnitType};
+
+use crate::AppState;
+
+impl AppState {
+    pub async fn list_call_natures(&self) -> Result<Vec<CallNature>, sqlx::Error> {
+        let natures = sqlx::query_as!(
+            CallNature,
+            r#"
+            SELECT code, description,
+                default_priority as "default_priority: Priority",
+                default_incident_type as "default_incident_type: IncidentType",
+                recommended_unit_types as "recommended_unit_types: Vec<UnitType>"
+            FROM call_natures
+            WHERE is_active
+            ORDER BY description
+            "#,
+        )
+        .fetch_all(&self.db)
+        .await?;
+
+        Ok(natures)
+    }
+
+    pub async fn get_call_nature(&self, code: &str) -> Result<Option<CallNature>, sqlx::Error> {
+        let nature = sqlx::query_as!(
+            CallNature,
+            r#"
+            SELECT code, description,
+                default_priority as "default_priority: Priority",
+                default_incident_type as "default_incident_type: IncidentType",
+                recommended_unit_types as "recommended_unit_types: Vec<UnitType>"
+            FROM call_natures
+            WHERE code = $1 AND is_active
+            "#,
+            code,
+        )
+        .fetch_optional(&self.db)
+        .await?;
+
+        Ok(nature)
+    }
+}
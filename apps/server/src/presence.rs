@@ -0,0 +1,145 @@
+//! Which dispatchers have a console open on each event, so every console can
+//! show who else is on shift.
+//!
+//! A console counts once its handshake is done, on the event it is following;
+//! whenever one joins, switches or disconnects, the events affected get a
+//! `PresenceChanged` with their full list.
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use shared_types::id::{EventId, UserId};
+use shared_types::presence::OnlineDispatcher;
+use shared_types::ProtocolMessage;
+
+use crate::protocol::Session;
+use crate::AppState;
+
+struct Console {
+    event_id: EventId,
+    user_id: UserId,
+    name: String,
+}
+
+/// Consoles on an event, by session.
+#[derive(Default)]
+pub struct Presence {
+    consoles: Mutex<HashMap<u64, Console>>,
+}
+
+impl Presence {
+    /// Puts a session's console on an event, or takes it off when `None`.
+    /// Returns the events whose list of dispatchers changed.
+    fn place(&self, session_id: u64, console: Option<Console>) -> Vec<EventId> {
+        let mut consoles = self.consoles.lock().unwrap();
+        let joined = console.as_ref().map(|console| console.event_id);
+        let left = match console {
+            Some(console) => consoles.insert(session_id, console),
+            None => consoles.remove(&session_id),
+        }
+        .map(|console| console.event_id);
+
+        if joined == left {
+            return Vec::new();
+        }
+        left.into_iter().chain(joined).collect()
+    }
+
+    /// Dispatchers with a console on `event_id`, by name.
+    pub fn online(&self, event_id: EventId) -> Vec<OnlineDispatcher> {
+        let consoles = self.consoles.lock().unwrap();
+        let mut online: Vec<OnlineDispatcher> = Vec::new();
+        for console in consoles.values().filter(|console| console.event_id == event_id) {
+            match online.iter_mut().find(|dispatcher| dispatcher.user_id == console.user_id) {
+                Some(dispatcher) => dispatcher.consoles += 1,
+                None => online.push(OnlineDispatcher {
+                    user_id: console.user_id,
+                    name: console.name.clone(),
+                    consoles: 1,
+                }),
+            }
+        }
+        online.sort_by(|a, b| a.name.cmp(&b.name));
+        online
+    }
+}
+
+impl AppState {
+    /// Records the event a session's console is on and tells the events it
+    /// joined and left who is online.
+    pub fn update_presence(&self, session: &Session) {
+        let console = session.active_event().map(|event_id| Console {
+            event_id,
+            user_id: session.user.id,
+            name: session.user.name.clone().unwrap_or_else(|| session.user.email.clone()),
+        });
+        self.announce_presence(self.presence.place(session.id, console));
+    }
+
+    /// Takes a disconnected session's console off its event.
+    pub fn leave_presence(&self, session: &Session) {
+        self.announce_presence(self.presence.place(session.id, None));
+    }
+
+    fn announce_presence(&self, event_ids: Vec<EventId>) {
+        for event_id in event_ids {
+            let online = self.presence.online(event_id);
+            self.broadcast(Some(event_id), ProtocolMessage::PresenceChanged { event_id, online });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sqlx::types::Uuid;
+
+    fn event(n: u128) -> EventId {
+        Uuid::from_u128(n).into()
+    }
+
+    fn console(event_id: u128, user: u128, name: &str) -> Option<Console> {
+        Some(Console {
+            event_id: event(event_id),
+            user_id: Uuid::from_u128(user).into(),
+            name: name.to_string(),
+        })
+    }
+
+    #[test]
+    fn joining_and_leaving_change_the_event() {
+        let presence = Presence::default();
+        assert_eq!(presence.place(1, console(10, 100, "Sam")), vec![event(10)]);
+        assert_eq!(presence.online(event(10)).len(), 1);
+
+        assert_eq!(presence.place(1, None), vec![event(10)]);
+        assert!(presence.online(event(10)).is_empty());
+        // Sessions that never joined leave nothing behind
+        assert!(presence.place(2, None).is_empty());
+    }
+
+    #[test]
+    fn switching_events_changes_both() {
+        let presence = Presence::default();
+        presence.place(1, console(10, 100, "Sam"));
+        assert_eq!(presence.place(1, console(20, 100, "Sam")), vec![event(10), event(20)]);
+        assert!(presence.online(event(10)).is_empty());
+        assert_eq!(presence.online(event(20))[0].name, "Sam");
+
+        // Rejoining the same event is not a change
+        assert!(presence.place(1, console(20, 100, "Sam")).is_empty());
+    }
+
+    #[test]
+    fn dispatchers_are_listed_once_by_name() {
+        let presence = Presence::default();
+        presence.place(1, console(10, 100, "Sam"));
+        presence.place(2, console(10, 200, "Alex"));
+        presence.place(3, console(10, 100, "Sam"));
+        presence.place(4, console(20, 300, "Jo"));
+
+        let online = presence.online(event(10));
+        let summary: Vec<_> = online.iter().map(|d| (d.name.as_str(), d.consoles)).collect();
+        assert_eq!(summary, vec![("Alex", 1), ("Sam", 2)]);
+    }
+}
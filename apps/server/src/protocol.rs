@@ -0,0 +1,740 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use shared_types::encoding::{Encoding, DEFLATE, MSGPACK};
+use shared_types::event::is_valid_time_zone;
+use shared_types::id::EventId;
+use shared_types::{ProtocolMessage, MIN_PROTOCOL_VERSION, PROTOCOL_VERSION};
+use sqlx::types::time::OffsetDateTime;
+use tokio::sync::watch;
+
+use crate::db::save_action_log;
+use crate::logging::{ActionLog, ActionType};
+use crate::map::validate_feature_collection;
+use crate::users::User;
+use crate::AppState;
+
+/// A server-originated message for every console, or only for those whose
+/// active event is `event_id`.
+#[derive(Debug, Clone)]
+pub struct Broadcast {
+    pub event_id: Option<EventId>,
+    pub message: ProtocolMessage,
+    /// Session that caused it, which already has the result in its reply
+    pub origin: Option<u64>,
+}
+
+impl AppState {
+    pub fn broadcast(&self, event_id: Option<EventId>, message: ProtocolMessage) {
+        let _ = self.broadcasts.send(Broadcast { event_id, message, origin: None });
+    }
+
+    /// Like [`AppState::broadcast`], but not echoed back to `session`.
+    pub fn broadcast_from(&self, session: &Session, event_id: Option<EventId>, message: ProtocolMessage) {
+        let _ = self.broadcasts.send(Broadcast { event_id, message, origin: Some(session.id) });
+    }
+}
+
+static NEXT_SESSION_ID: AtomicU64 = AtomicU64::new(1);
+
+/// Optional features this server can enable for a connection, by name.
+const SERVER_CAPABILITIES: &[&str] = &[MSGPACK, DEFLATE];
+
+/// Per-connection state for a console.
+pub struct Session {
+    /// Unique for the life of the server
+    pub id: u64,
+    pub user: User,
+    active_event: watch::Sender<Option<EventId>>,
+    /// Negotiated by `Hello`; `None` until the client's first message
+    protocol_version: Option<u32>,
+    /// JSON text until the `Welcome` enabling a binary encoding has been sent
+    encoding: watch::Sender<Encoding>,
+}
+
+impl Session {
+    pub fn new(user: User, active_event: Option<EventId>) -> Self {
+        Self {
+            id: NEXT_SESSION_ID.fetch_add(1, Ordering::Relaxed),
+            user,
+            active_event: watch::Sender::new(active_event),
+            protocol_version: None,
+            encoding: watch::Sender::new(Encoding::default()),
+        }
+    }
+
+    pub fn active_event(&self) -> Option<EventId> {
+        *self.active_event.borrow()
+    }
+
+    /// Follows the active event so broadcasts can be filtered as it changes.
+    pub fn watch_active_event(&self) -> watch::Receiver<Option<EventId>> {
+        self.active_event.subscribe()
+    }
+
+    pub fn encoding(&self) -> Encoding {
+        *self.encoding.borrow()
+    }
+
+    /// Follows the encoding so broadcasts switch with the rest of the connection.
+    pub fn watch_encoding(&self) -> watch::Receiver<Encoding> {
+        self.encoding.subscribe()
+    }
+
+    /// Switches encodings; call only once the `Welcome` has been queued, so the
+    /// client sees it as text before any binary frames.
+    pub fn set_encoding(&self, encoding: Encoding) {
+        self.encoding.send_replace(encoding);
+    }
+
+    fn set_active_event(&self, event_id: EventId) {
+        self.active_event.send_replace(Some(event_id));
+    }
+
+    /// Only managers may look outside their active event.
+    fn can_access_event(&self, event_id: EventId) -> bool {
+        self.user.is_supervisor() || self.active_event() == Some(event_id)
+    }
+}
+
+fn error(message: impl Into<String>) -> ProtocolMessage {
+    ProtocolMessage::Error {
+        message: message.into(),
+    }
+}
+
+fn no_active_event() -> ProtocolMessage {
+    error("No active event selected")
+}
+
+/// Version spoken by clients that open without a `Hello`.
+const PRE_HELLO_VERSION: u32 = 1;
+
+/// Tells the user which side needs upgrading when no common version exists.
+fn unsupported_version(client_max_version: u32) -> ProtocolMessage {
+    let message = if client_max_version < MIN_PROTOCOL_VERSION {
+        "This version of Rover is too old for the dispatch server. Please update Rover to continue."
+    } else {
+        "The dispatch server is older than this version of Rover. Ask an administrator to upgrade the server."
+    };
+    ProtocolMessage::UnsupportedVersion {
+        min_version: MIN_PROTOCOL_VERSION,
+        max_version: PROTOCOL_VERSION,
+        message: message.to_string(),
+    }
+}
+
+/// The session's starting event, sent after the `Welcome` as the console has no
+/// way to ask for it.
+pub async fn active_event(state: &AppState, session: &Session) -> Option<ProtocolMessage> {
+    let event_id = session.active_event()?;
+    match state.get_event(event_id).await {
+        Ok(event) => event.map(|event| ProtocolMessage::ActiveEvent { event }),
+        Err(e) => {
+            tracing::error!("Failed to load event {} for {}: {}", event_id, session.user.email, e);
+            None
+        }
+    }
+}
+
+/// Records an action in the audit log; failures are logged but never fail the request.
+async fn log_action(state: &AppState, session: &Session, action_type: ActionType, details: String) {
+    let log = ActionLog {
+        timestamp: OffsetDateTime::now_utc(),
+        action_type,
+        user_id: Some(session.user.id),
+        event_id: session.active_event(),
+        ip_address: None,
+        details,
+    };
+    if let Err(e) = save_action_log(&state.db, &log).await {
+        tracing::error!("Failed to save action log: {}", e);
+    }
+}
+
+/// Handles a single protocol message from a client, returning the reply (if any).
+pub async fn handle_message(
+    state: &AppState,
+    session: &mut Session,
+    message: ProtocolMessage,
+) -> Option<ProtocolMessage> {
+    if session.protocol_version.is_none() && !matches!(message, ProtocolMessage::Hello { .. }) {
+        tracing::warn!("{} sent no hello; rejecting protocol v{}", session.user.email, PRE_HELLO_VERSION);
+        return Some(unsupported_version(PRE_HELLO_VERSION));
+    }
+
+    let user = &session.user;
+    let active_event = session.active_event();
+
+    match message {
+        ProtocolMessage::Hello { min_version, max_version, client, capabilities } => {
+            if session.protocol_version.is_some() {
+                return Some(error("Hello may only be sent once per connection"));
+            }
+            let version = max_version.min(PROTOCOL_VERSION);
+            if version < min_version.max(MIN_PROTOCOL_VERSION) {
+                tracing::warn!(
+                    "{} connected with {} speaking protocol v{}-v{}; rejecting",
+                    user.email,
+                    client,
+                    min_version,
+                    max_version
+                );
+                return Some(unsupported_version(max_version));
+            }
+
+            tracing::info!("{} connected with {} speaking protocol v{}", user.email, client, version);
+            session.protocol_version = Some(version);
+            Some(ProtocolMessage::Welcome {
+                version,
+                server: concat!("dog-house/", env!("CARGO_PKG_VERSION")).to_string(),
+                capabilities: capabilities
+                    .into_iter()
+                    .filter(|capability| SERVER_CAPABILITIES.contains(&capability.as_str()))
+                    .collect(),
+            })
+        }
+        ProtocolMessage::ListEvents => Some(match state.list_events().await {
+            Ok(events) => ProtocolMessage::Events { events },
+            Err(e) => error(format!("Database error: {}", e)),
+        }),
+        ProtocolMessage::CreateEvent { event } => {
+            if !user.is_supervisor() {
+                return Some(error("Only a manager can create events"));
+            }
+            if !is_valid_time_zone(&event.time_zone) {
+                return Some(error(format!("Unknown time zone: {}", event.time_zone)));
+            }
+            Some(match state.create_event(&event).await {
+                Ok(event) => ProtocolMessage::Event { event },
+                Err(e) => error(format!("Database error: {}", e)),
+            })
+        }
+        ProtocolMessage::SetActiveEvent { event_id } => {
+            Some(match state.get_event(event_id).await {
+                Ok(Some(event)) => {
+                    session.set_active_event(event_id);
+                    state.update_presence(session);
+                    ProtocolMessage::ActiveEvent { event }
+                }
+                Ok(None) => error(format!("Event {} not found", event_id)),
+                Err(e) => error(format!("Database error: {}", e)),
+            })
+        }
+        ProtocolMessage::GetActiveCalls => {
+            let Some(event_id) = active_event else {
+                return Some(no_active_event());
+            };
+            Some(match state.active_calls(event_id).await {
+                Ok(calls) => ProtocolMessage::ActiveCalls { calls },
+                Err(e) => error(format!("Database error: {}", e)),
+            })
+        }
+        ProtocolMessage::GetEventCalls { event_id } => {
+            if !session.can_access_event(event_id) {
+                return Some(error("Only a manager can query other events"));
+            }
+            Some(match state.event_calls(event_id).await {
+                Ok(calls) => ProtocolMessage::EventCalls { event_id, calls },
+                Err(e) => error(format!("Database error: {}", e)),
+            })
+        }
+        ProtocolMessage::GetCallsOnDay { date } => {
+            let Some(event_id) = active_event else {
+                return Some(no_active_event());
+            };
+            let event = match state.get_event(event_id).await {
+                Ok(Some(event)) => event,
+                Ok(None) => return Some(no_active_event()),
+                Err(e) => return Some(error(format!("Database error: {}", e))),
+            };
+            let (from, until) = event.day_bounds(date);
+            Some(match state.calls_received_between(event_id, from, until).await {
+                Ok(calls) => ProtocolMessage::CallsOnDay { date, calls },
+                Err(e) => error(format!("Database error: {}", e)),
+            })
+        }
+        ProtocolMessage::GetCallNatures => Some(match state.list_call_natures().await {
+            Ok(natures) => ProtocolMessage::CallNatures { natures },
+            Err(e) => error(format!("Database error: {}", e)),
+        }),
+        ProtocolMessage::CreateCall { call } => {
+            let Some(event_id) = active_event else {
+                return Some(no_active_event());
+            };
+            let nature = match state.get_call_nature(&call.nature_code).await {
+                Ok(Some(nature)) => nature,
+                Ok(None) => return Some(error(format!("Unknown call nature: {}", call.nature_code))),
+                Err(e) => return Some(error(format!("Database error: {}", e))),
+            };
+
+            tracing::info!("{} creating {} call", user.email, nature.code);
+            let call = match state.create_incident(event_id, &call, &nature).await {
+                Ok(Some(call)) => call,
+                Ok(None) => return Some(error("The location must belong to the active event")),
+                Err(e) => return Some(error(format!("Database error: {}", e))),
+            };
+
+            log_action(state, session, ActionType::CreateCall, format!("Created {}", call.incident_number)).await;
+            state.broadcast_from(session, Some(event_id), ProtocolMessage::CallUpdated { call: call.clone() });
+            Some(ProtocolMessage::Call { call })
+        }
+        ProtocolMessage::GetCall { id } => {
+            Some(match state.get_incident(id).await {
+                Ok(Some(call)) if session.can_access_event(call.event_id) => ProtocolMessage::Call { call },
+                Ok(Some(_)) => error("Only a manager can view calls from other events"),
+                Ok(None) => error(format!("Call {} not found", id)),
+                Err(e) => error(format!("Database error: {}", e)),
+            })
+        }
+        ProtocolMessage::UpdateCall { id, update } => {
+            let Some(event_id) = active_event else {
+                return Some(no_active_event());
+            };
+            let call = match state.update_incident(event_id, id, &update).await {
+                Ok(Some(call)) => call,
+                Ok(None) => {
+                    return Some(error(match update.location_id {
+                        Some(Some(location_id)) => format!("Call {} or location {} not found in the active event", id, location_id),
+                        _ => format!("Call {} not found", id),
+                    }))
+                }
+                Err(e) => return Some(error(format!("Database error: {}", e))),
+            };
+
+            log_action(state, session, ActionType::UpdateCall, format!("Updated {}", call.incident_number)).await;
+            state.broadcast_from(session, Some(event_id), ProtocolMessage::CallUpdated { call: call.clone() });
+            Some(ProtocolMessage::Call { call })
+        }
+        ProtocolMessage::AddNote { incident_id, content } => {
+            let Some(event_id) = active_event else {
+                return Some(no_active_event());
+            };
+            if content.trim().is_empty() {
+                return Some(error("A note needs some text"));
+            }
+            let call = match state.add_note(event_id, incident_id, user.id, &content).await {
+                Ok(Some(call)) => call,
+                Ok(None) => return Some(error(format!("Call {} not found", incident_id))),
+                Err(e) => return Some(error(format!("Database error: {}", e))),
+            };
+
+            log_action(state, session, ActionType::AddNote, format!("Note on {}", call.incident_number)).await;
+            state.broadcast_from(session, Some(event_id), ProtocolMessage::CallUpdated { call: call.clone() });
+            Some(ProtocolMessage::Call { call })
+        }
+        ProtocolMessage::GetPendingQueue => {
+            let Some(event_id) = active_event else {
+                return Some(no_active_event());
+            };
+            Some(match state.pending_queue(event_id).await {
+                Ok(calls) => ProtocolMessage::PendingQueue { calls },
+                Err(e) => error(format!("Database error: {}", e)),
+            })
+        }
+        ProtocolMessage::GetUnits => {
+            let Some(event_id) = active_event else {
+                return Some(no_active_event());
+            };
+            Some(match state.list_units(event_id).await {
+                Ok(units) => ProtocolMessage::Units { units },
+                Err(e) => error(format!("Database error: {}", e)),
+            })
+        }
+        ProtocolMessage::UpdateUnitStatus { unit_id, status, location } => {
+            let Some(event_id) = active_event else {
+                return Some(no_active_event());
+            };
+            match state.set_unit_status(event_id, unit_id, status, location.as_deref()).await {
+                Ok(Some(unit)) => {
+                    log_action(state, session, ActionType::UnitStatusChange, format!("{} -> {:?}", unit.name, status)).await;
+                    state.broadcast(Some(event_id), ProtocolMessage::UnitUpdated { unit });
+                    None
+                }
+                Ok(None) => Some(error(format!("Unit {} not found", unit_id))),
+                Err(e) => Some(error(format!("Database error: {}", e))),
+            }
+        }
+        ProtocolMessage::CheckInUnit { unit_id } => {
+            let Some(event_id) = active_event else {
+                return Some(no_active_event());
+            };
+            match state.check_in_unit(event_id, unit_id).await {
+                Ok(Some(unit)) => {
+                    log_action(state, session, ActionType::UnitCheckIn, format!("{} checked in ({:?})", unit.name, unit.status)).await;
+                    state.broadcast(Some(event_id), ProtocolMessage::UnitUpdated { unit });
+                    None
+                }
+                Ok(None) => Some(error(format!("Unit {} not found", unit_id))),
+                Err(e) => Some(error(format!("Database error: {}", e))),
+            }
+        }
+        ProtocolMessage::ActivateEmergency { unit_id, location } => {
+            let Some(event_id) = active_event else {
+                return Some(no_active_event());
+            };
+            let activation = match state.activate_emergency(event_id, unit_id, location.as_deref()).await {
+                Ok(Some(activation)) => activation,
+                Ok(None) => return Some(error(format!("Unit {} not found", unit_id))),
+                Err(e) => return Some(error(format!("Database error: {}", e))),
+            };
+            let unit = match state.get_unit(event_id, unit_id).await {
+                Ok(Some(unit)) => unit,
+                Ok(None) => return Some(error(format!("Unit {} not found", unit_id))),
+                Err(e) => return Some(error(format!("Database error: {}", e))),
+            };
+
+            tracing::warn!("EMERGENCY activated by {} at {}", unit.name, activation.location);
+            log_action(
+                state,
+                session,
+                ActionType::EmergencyActivated,
+                format!("{} at {} (incident {})", unit.name, activation.location, activation.incident_id),
+            )
+            .await;
+            state.broadcast(
+                Some(event_id),
+                ProtocolMessage::EmergencyAlert {
+                    unit,
+                    incident_id: activation.incident_id,
+                    location: activation.location,
+                    audible: true,
+                },
+            );
+            None
+        }
+        ProtocolMessage::AcknowledgeEmergency { unit_id } => {
+            if !user.is_supervisor() {
+                return Some(error("Only a supervisor can acknowledge an emergency"));
+            }
+            let Some(event_id) = active_event else {
+                return Some(no_active_event());
+            };
+            match state.acknowledge_emergency(event_id, unit_id, user.id).await {
+                Ok(true) => {
+                    log_action(state, session, ActionType::EmergencyAcknowledged, format!("Unit {}", unit_id)).await;
+                    state.broadcast(
+                        Some(event_id),
+                        ProtocolMessage::EmergencyAcknowledged {
+                            unit_id,
+                            acknowledged_by: user.email.clone(),
+                        },
+                    );
+                    None
+                }
+                Ok(false) => Some(error(format!("Unit {} has no active emergency", unit_id))),
+                Err(e) => Some(error(format!("Database error: {}", e))),
+            }
+        }
+        ProtocolMessage::ClearEmergency { unit_id } => {
+            if !user.is_supervisor() {
+                return Some(error("Only a supervisor can clear an emergency"));
+            }
+            let Some(event_id) = active_event else {
+                return Some(no_active_event());
+            };
+            match state.clear_emergency(event_id, unit_id).await {
+                Ok(true) => {
+                    log_action(state, session, ActionType::EmergencyCleared, format!("Unit {}", unit_id)).await;
+                    state.broadcast(
+                        Some(event_id),
+                        ProtocolMessage::EmergencyCleared {
+                            unit_id,
+                            cleared_by: user.email.clone(),
+                        },
+                    );
+                    None
+                }
+                Ok(false) => Some(error(format!(
+                    "Unit {} has no acknowledged emergency to clear",
+                    unit_id
+                ))),
+                Err(e) => Some(error(format!("Database error: {}", e))),
+            }
+        }
+        ProtocolMessage::StartRollCall { reason } => {
+            if !user.is_supervisor() {
+                return Some(error("Only a supervisor can start a roll call"));
+            }
+            let Some(event_id) = active_event else {
+                return Some(no_active_event());
+            };
+            match state.start_roll_call(event_id, &reason, user.id).await {
+                Ok(roll_call) => {
+                    log_action(
+                        state,
+                        session,
+                        ActionType::RollCallStarted,
+                        format!("{} ({} units): {}", roll_call.id, roll_call.entries.len(), reason),
+                    )
+                    .await;
+                    state.broadcast(
+                        Some(event_id),
+                        ProtocolMessage::RollCallAlert {
+                            roll_call_id: roll_call.id,
+                            outstanding: roll_call.outstanding(),
+                        },
+                    );
+                    state.broadcast(Some(event_id), ProtocolMessage::RollCallUpdated { roll_call });
+                    None
+                }
+                Err(e) => Some(error(format!("Database error: {}", e))),
+            }
+        }
+        ProtocolMessage::MarkUnitAccounted { roll_call_id, unit_id } => {
+            let Some(event_id) = active_event else {
+                return Some(no_active_event());
+            };
+            match state.mark_unit_accounted(event_id, roll_call_id, unit_id, user.id).await {
+                Ok(true) => {}
+                Ok(false) => return Some(error(format!("Unit {} is not outstanding in an open roll call", unit_id))),
+                Err(e) => return Some(error(format!("Database error: {}", e))),
+            }
+            match state.get_roll_call(roll_call_id).await {
+                Ok(Some(roll_call)) => {
+                    state.broadcast(Some(event_id), ProtocolMessage::RollCallUpdated { roll_call });
+                    None
+                }
+                Ok(None) => Some(error(format!("Roll call {} not found", roll_call_id))),
+                Err(e) => Some(error(format!("Database error: {}", e))),
+            }
+        }
+        ProtocolMessage::RealertRollCall { roll_call_id } => {
+            if !user.is_supervisor() {
+                return Some(error("Only a supervisor can re-alert a roll call"));
+            }
+            let Some(event_id) = active_event else {
+                return Some(no_active_event());
+            };
+            match state.get_roll_call(roll_call_id).await {
+                Ok(Some(roll_call)) if roll_call.event_id != event_id => {
+                    Some(error(format!("Roll call {} not found", roll_call_id)))
+                }
+                Ok(Some(roll_call)) if roll_call.completed_at.is_none() => {
+                    state.broadcast(
+                        Some(event_id),
+                        ProtocolMessage::RollCallAlert {
+                            roll_call_id,
+                            outstanding: roll_call.outstanding(),
+                        },
+                    );
+                    None
+                }
+                Ok(Some(_)) => Some(error(format!("Roll call {} has already completed", roll_call_id))),
+                Ok(None) => Some(error(format!("Roll call {} not found", roll_call_id))),
+                Err(e) => Some(error(format!("Database error: {}", e))),
+            }
+        }
+        ProtocolMessage::CompleteRollCall { roll_call_id } => {
+            if !user.is_supervisor() {
+                return Some(error("Only a supervisor can complete a roll call"));
+            }
+            let Some(event_id) = active_event else {
+                return Some(no_active_event());
+            };
+            match state.complete_roll_call(event_id, roll_call_id, user.id).await {
+                Ok(true) => {}
+                Ok(false) => return Some(error(format!("Roll call {} is not open", roll_call_id))),
+                Err(e) => return Some(error(format!("Database error: {}", e))),
+            }
+            match state.get_roll_call(roll_call_id).await {
+                Ok(Some(roll_call)) => {
+                    log_action(
+                        state,
+                        session,
+                        ActionType::RollCallCompleted,
+                        format!("{} with {} units outstanding", roll_call.id, roll_call.outstanding().len()),
+                    )
+                    .await;
+                    state.broadcast(Some(event_id), ProtocolMessage::RollCallUpdated { roll_call });
+                    None
+                }
+                Ok(None) => Some(error(format!("Roll call {} not found", roll_call_id))),
+                Err(e) => Some(error(format!("Database error: {}", e))),
+            }
+        }
+        ProtocolMessage::GetRollCalls => {
+            let Some(event_id) = active_event else {
+                return Some(no_active_event());
+            };
+            Some(match state.list_roll_calls(event_id).await {
+                Ok(roll_calls) => ProtocolMessage::RollCalls { roll_calls },
+                Err(e) => error(format!("Database error: {}", e)),
+            })
+        }
+        ProtocolMessage::GetLocations => {
+            let Some(event_id) = active_event else {
+                return Some(no_active_event());
+            };
+            Some(match state.list_locations(event_id).await {
+                Ok(locations) => ProtocolMessage::Locations { locations },
+                Err(e) => error(format!("Database error: {}", e)),
+            })
+        }
+        ProtocolMessage::ResolveLocation { query } => {
+            let Some(event_id) = active_event else {
+                return Some(no_active_event());
+            };
+            Some(match state.resolve_location(event_id, &query).await {
+                Ok(matches) => ProtocolMessage::LocationMatches { query, matches },
+                Err(e) => error(format!("Database error: {}", e)),
+            })
+        }
+        ProtocolMessage::CreateLocation { location } => {
+            if !user.is_supervisor() {
+                return Some(error("Only a supervisor can register locations"));
+            }
+            let Some(event_id) = active_event else {
+                return Some(no_active_event());
+            };
+            Some(match state.create_location(event_id, &location).await {
+                Ok(Some(location)) => ProtocolMessage::Location { location },
+                Ok(None) => error("The parent location must belong to the active event"),
+                Err(e) => error(format!("Database error: {}", e)),
+            })
+        }
+        ProtocolMessage::ImportMapLayer { name, geojson } => {
+            if !user.is_admin() {
+                return Some(error("Only an admin can import venue maps"));
+            }
+            let Some(event_id) = active_event else {
+                return Some(no_active_event());
+            };
+            if let Err(e) = validate_feature_collection(&geojson) {
+                return Some(error(e));
+            }
+            Some(match state.import_map_layer(event_id, &name, &geojson, user.id).await {
+                Ok(located) => ProtocolMessage::MapLayerImported { name, located },
+                Err(e) => error(format!("Database error: {}", e)),
+            })
+        }
+        ProtocolMessage::SetLocationPosition { location_id, position } => {
+            if !user.is_supervisor() {
+                return Some(error("Only a supervisor can position locations"));
+            }
+            let Some(event_id) = active_event else {
+                return Some(no_active_event());
+            };
+            Some(match state.set_location_position(event_id, location_id, position).await {
+                Ok(Some(location)) => ProtocolMessage::Location { location },
+                Ok(None) => error(format!("Location {} not found", location_id)),
+                Err(e) => error(format!("Database error: {}", e)),
+            })
+        }
+        ProtocolMessage::UpdateUnitPosition { unit_id, position } => {
+            let Some(event_id) = active_event else {
+                return Some(no_active_event());
+            };
+            match state.update_unit_position(event_id, unit_id, position).await {
+                Ok(Some(unit)) => {
+                    state.broadcast(Some(event_id), ProtocolMessage::UnitUpdated { unit });
+                    None
+                }
+                Ok(None) => Some(error(format!("Unit {} not found", unit_id))),
+                Err(e) => Some(error(format!("Database error: {}", e))),
+            }
+        }
+        ProtocolMessage::GetMapLayers => {
+            let Some(event_id) = active_event else {
+                return Some(no_active_event());
+            };
+            Some(match state.map_layers(event_id).await {
+                Ok(layers) => ProtocolMessage::MapLayers { layers },
+                Err(e) => error(format!("Database error: {}", e)),
+            })
+        }
+        ProtocolMessage::CreateLocationLink { link } => {
+            if !user.is_supervisor() {
+                return Some(error("Only a supervisor can edit the walking graph"));
+            }
+            let Some(event_id) = active_event else {
+                return Some(no_active_event());
+            };
+            Some(match state.create_location_link(event_id, &link).await {
+                Ok(Some(link)) => ProtocolMessage::LocationLinks { links: vec![link] },
+                Ok(None) => error("Both locations must belong to the active event"),
+                Err(e) => error(format!("Database error: {}", e)),
+            })
+        }
+        ProtocolMessage::GetLocationLinks => {
+            let Some(event_id) = active_event else {
+                return Some(no_active_event());
+            };
+            Some(match state.list_location_links(event_id).await {
+                Ok(links) => ProtocolMessage::LocationLinks { links },
+                Err(e) => error(format!("Database error: {}", e)),
+            })
+        }
+        ProtocolMessage::GetRoute { from_location_id, to_location_id, accessible_only } => {
+            let Some(event_id) = active_event else {
+                return Some(no_active_event());
+            };
+            Some(match state.walk_graph(event_id, accessible_only).await {
+                Ok(graph) => ProtocolMessage::Route {
+                    route: graph.routes_to(to_location_id).route_from(from_location_id),
+                },
+                Err(e) => error(format!("Database error: {}", e)),
+            })
+        }
+        ProtocolMessage::UpdateUnitLocation { unit_id, location_id } => {
+            let Some(event_id) = active_event else {
+                return Some(no_active_event());
+            };
+            match state.set_unit_location(event_id, unit_id, location_id).await {
+                Ok(Some(unit)) => {
+                    state.broadcast(Some(event_id), ProtocolMessage::UnitUpdated { unit });
+                    None
+                }
+                Ok(None) => Some(error(format!("Unit {} or location {} not found", unit_id, location_id))),
+                Err(e) => Some(error(format!("Database error: {}", e))),
+            }
+        }
+        ProtocolMessage::RecommendUnits { incident_id, accessible_only } => {
+            let Some(event_id) = active_event else {
+                return Some(no_active_event());
+            };
+            let incident = match state.get_incident(incident_id).await {
+                Ok(Some(incident)) if incident.event_id == event_id => incident,
+                Ok(_) => return Some(error(format!("Call {} not found", incident_id))),
+                Err(e) => return Some(error(format!("Database error: {}", e))),
+            };
+            Some(match state.recommend_units(event_id, &incident, accessible_only).await {
+                Ok(recommendations) => ProtocolMessage::UnitRecommendations { incident_id, recommendations },
+                Err(e) => error(format!("Database error: {}", e)),
+            })
+        }
+        ProtocolMessage::DispatchUnit { incident_id, unit_id } => {
+            let Some(event_id) = active_event else {
+                return Some(no_active_event());
+            };
+            let call = match state.dispatch_unit(event_id, incident_id, unit_id).await {
+                Ok(Some(call)) => call,
+                Ok(None) => return Some(error(format!("Call {} or unit {} not found", incident_id, unit_id))),
+                Err(e) => return Some(error(format!("Database error: {}", e))),
+            };
+
+            if let Some(assigned) = call.units_assigned.iter().find(|assigned| assigned.unit.id == unit_id) {
+                log_action(
+                    state,
+                    session,
+                    ActionType::DispatchUnit,
+                    format!("{} to {} (ETA {:?}s)", assigned.unit.name, call.incident_number, assigned.eta_secs),
+                )
+                .await;
+                state.broadcast(
+                    Some(event_id),
+                    ProtocolMessage::UnitUpdated {
+                        unit: assigned.unit.clone(),
+                    },
+                );
+            }
+            state.broadcast(Some(event_id), ProtocolMessage::CallUpdated { call });
+            None
+        }
+        // Answered with an `Ack` when it carries a request ID
+        ProtocolMessage::Ping => None,
+        other => {
+            tracing::debug!("Unhandled protocol message: {:?}", other);
+            Some(error(format!("Unsupported request: {}", other.message_type())))
+        }
+    }
+}
@@ -0,0 +1,101 @@
+use std::{sync::Arc, time::Duration};
+
+use shared_types::id::{EventId, IncidentId};
+use shared_types::{incident::Priority, ProtocolMessage};
+
+use crate::AppState;
+
+/// How long a pending call may wait, per priority, before it is escalated.
+#[derive(Debug, Clone)]
+pub struct QueueConfig {
+    pub high_threshold: Duration,
+    pub medium_threshold: Duration,
+    pub low_threshold: Duration,
+    pub check_interval: Duration,
+}
+
+impl QueueConfig {
+    /// Reads `QUEUE_ESCALATE_{HIGH,MEDIUM,LOW}_SECS` and `QUEUE_CHECK_INTERVAL_SECS`
+    /// (at least 1), falling back to 1, 3 and 10 minutes checked every 15 seconds.
+    pub fn from_env() -> Self {
+        fn secs(var: &str, default: u64) -> Duration {
+            let secs = dotenvy::var(var)
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(default);
+            Duration::from_secs(secs)
+        }
+
+        Self {
+            high_threshold: secs("QUEUE_ESCALATE_HIGH_SECS", 60),
+            medium_threshold: secs("QUEUE_ESCALATE_MEDIUM_SECS", 180),
+            low_threshold: secs("QUEUE_ESCALATE_LOW_SECS", 600),
+            // A zero interval would panic the escalation task
+            check_interval: secs("QUEUE_CHECK_INTERVAL_SECS", 15).max(Duration::from_secs(1)),
+        }
+    }
+}
+
+struct OverdueCall {
+    id: IncidentId,
+    event_id: EventId,
+    priority: Priority,
+    waiting_secs: i64,
+}
+
+impl AppState {
+    /// Marks pending calls that have waited past their threshold as escalated and
+    /// returns them. Each call is only escalated once.
+    async fn escalate_overdue_calls(&self) -> Result<Vec<OverdueCall>, sqlx::Error> {
+        let calls = sqlx::query_as!(
+            OverdueCall,
+            r#"
+            UPDATE incidents
+            SET escalated_at = NOW()
+            WHERE assigned_at IS NULL
+                AND cleared_at IS NULL
+                AND escalated_at IS NULL
+                AND received_at < NOW() - INTERVAL '1 second' * CASE priority
+                    WHEN 'high' THEN $1::FLOAT8
+                    WHEN 'medium' THEN $2::FLOAT8
+                    ELSE $3::FLOAT8
+                END
+            RETURNING id as "id: IncidentId", event_id as "event_id: EventId", priority as "priority: Priority",
+                EXTRACT(EPOCH FROM NOW() - received_at)::BIGINT as "waiting_secs!"
+            "#,
+            self.queue_config.high_threshold.as_secs_f64(),
+            self.queue_config.medium_threshold.as_secs_f64(),
+            self.queue_config.low_threshold.as_secs_f64(),
+        )
+        .fetch_all(&self.db)
+        .await?;
+
+        Ok(calls)
+    }
+}
+
+/// Periodically escalates overdue pending calls and broadcasts a `CallEscalated`
+/// event for each one.
+pub async fn run_escalation(state: Arc<AppState>) {
+    let mut interval = tokio::time::interval(state.queue_config.check_interval);
+    loop {
+        interval.tick().await;
+        match state.escalate_overdue_calls().await {
+            Ok(calls) => {
+                for call in calls {
+                    tracing::warn!("Call {} escalated after waiting {}s", call.id, call.waiting_secs);
+                    // Nobody listening is fine; the call stays escalated in the queue.
+                    state.broadcast(
+                        Some(call.event_id),
+                        ProtocolMessage::CallEscalated {
+                            id: call.id,
+                            priority: call.priority,
+                            waiting_secs: call.waiting_secs,
+                        },
+                    );
+                }
+            }
+            Err(e) => tracing::error!("Failed to check pending queue: {}", e),
+        }
+    }
+}
@@ -0,0 +1,243 @@
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
+
+use shared_types::id::{EventId, LocationId};
+use shared_types::routing::{LinkKind, LocationLink, NewLocationLink, Route};
+use sqlx::types::Uuid;
+
+use crate::AppState;
+
+struct LinkRow {
+    id: Uuid,
+    event_id: Uuid,
+    from_location_id: Uuid,
+    to_location_id: Uuid,
+    kind: LinkKind,
+    travel_secs: i32,
+    accessible: bool,
+    bidirectional: bool,
+}
+
+impl From<LinkRow> for LocationLink {
+    fn from(row: LinkRow) -> Self {
+        LocationLink {
+            id: row.id.into(),
+            event_id: row.event_id.into(),
+            from_location_id: row.from_location_id.into(),
+            to_location_id: row.to_location_id.into(),
+            kind: row.kind,
+            travel_secs: row.travel_secs as u32,
+            accessible: row.accessible,
+            bidirectional: row.bidirectional,
+        }
+    }
+}
+
+/// Walkable graph for one event, stored reversed so a single search from the
+/// destination yields routes from every starting point.
+pub struct WalkGraph {
+    reverse_edges: HashMap<LocationId, Vec<(LocationId, u32)>>,
+}
+
+/// Shortest routes from any reachable location to one destination.
+pub struct RoutesTo {
+    target: LocationId,
+    eta: HashMap<LocationId, u32>,
+    next_hop: HashMap<LocationId, LocationId>,
+}
+
+impl WalkGraph {
+    fn new(links: &[LinkRow], accessible_only: bool) -> Self {
+        let mut reverse_edges: HashMap<LocationId, Vec<(LocationId, u32)>> = HashMap::new();
+        for link in links.iter().filter(|link| link.accessible || !accessible_only) {
+            let secs = link.travel_secs.max(0) as u32;
+            reverse_edges
+                .entry(link.to_location_id.into())
+                .or_default()
+                .push((link.from_location_id.into(), secs));
+            if link.bidirectional {
+                reverse_edges
+                    .entry(link.from_location_id.into())
+                    .or_default()
+                    .push((link.to_location_id.into(), secs));
+            }
+        }
+        Self { reverse_edges }
+    }
+
+    /// Dijkstra from `target` over reversed links.
+    pub fn routes_to(&self, target: LocationId) -> RoutesTo {
+        let mut eta = HashMap::from([(target, 0)]);
+        let mut next_hop = HashMap::new();
+        let mut queue = BinaryHeap::from([Reverse((0u32, target))]);
+
+        while let Some(Reverse((secs, location))) = queue.pop() {
+            if eta.get(&location).is_some_and(|best| secs > *best) {
+                continue;
+            }
+            for &(previous, link_secs) in self.reverse_edges.get(&location).into_iter().flatten() {
+                let candidate = secs.saturating_add(link_secs);
+                if eta.get(&previous).is_none_or(|best| candidate < *best) {
+                    eta.insert(previous, candidate);
+                    next_hop.insert(previous, location);
+                    queue.push(Reverse((candidate, previous)));
+                }
+            }
+        }
+
+        RoutesTo { target, eta, next_hop }
+    }
+}
+
+impl RoutesTo {
+    pub fn route_from(&self, from: LocationId) -> Option<Route> {
+        let eta_secs = *self.eta.get(&from)?;
+        let mut location_ids = vec![from];
+        let mut current = from;
+        while current != self.target {
+            current = self.next_hop[&current];
+            location_ids.push(current);
+        }
+        Some(Route { location_ids, eta_secs })
+    }
+}
+
+impl AppState {
+    /// Adds a link between two locations of the event. Returns `None` if either
+    /// location belongs to a different event.
+    pub async fn create_location_link(
+        &self,
+        event_id: EventId,
+        link: &NewLocationLink,
+    ) -> Result<Option<LocationLink>, sqlx::Error> {
+        let link = sqlx::query_as!(
+            LinkRow,
+            r#"
+            INSERT INTO location_links
+            (event_id, from_location_id, to_location_id, kind, travel_secs, accessible, bidirectional)
+            SELECT $1, f.id, t.id, $4, $5, $6, $7
+            FROM locations f, locations t
+            WHERE f.id = $2 AND t.id = $3 AND f.event_id = $1 AND t.event_id = $1
+            RETURNING id, event_id, from_location_id, to_location_id, kind as "kind: LinkKind",
+                travel_secs, accessible, bidirectional
+            "#,
+            event_id as EventId,
+            link.from_location_id as LocationId,
+            link.to_location_id as LocationId,
+            link.kind as LinkKind,
+            link.travel_secs as i32,
+            link.accessible,
+            link.bidirectional,
+        )
+        .fetch_optional(&self.db)
+        .await?;
+
+        Ok(link.map(LocationLink::from))
+    }
+
+    async fn location_link_rows(&self, event_id: EventId) -> Result<Vec<LinkRow>, sqlx::Error> {
+        sqlx::query_as!(
+            LinkRow,
+            r#"
+            SELECT id, event_id, from_location_id, to_location_id, kind as "kind: LinkKind",
+                travel_secs, accessible, bidirectional
+            FROM location_links
+            WHERE event_id = $1
+            "#,
+            event_id as EventId,
+        )
+        .fetch_all(&self.db)
+        .await
+    }
+
+    pub async fn list_location_links(&self, event_id: EventId) -> Result<Vec<LocationLink>, sqlx::Error> {
+        let links = self.location_link_rows(event_id).await?;
+        Ok(links.into_iter().map(LocationLink::from).collect())
+    }
+
+    pub async fn walk_graph(&self, event_id: EventId, accessible_only: bool) -> Result<WalkGraph, sqlx::Error> {
+        let links = self.location_link_rows(event_id).await?;
+        Ok(WalkGraph::new(&links, accessible_only))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn location(n: u128) -> Uuid {
+        Uuid::from_u128(n)
+    }
+
+    fn link(from: u128, to: u128, kind: LinkKind, travel_secs: i32, accessible: bool, bidirectional: bool) -> LinkRow {
+        LinkRow {
+            id: Uuid::nil(),
+            event_id: location(0),
+            from_location_id: location(from),
+            to_location_id: location(to),
+            kind,
+            travel_secs,
+            accessible,
+            bidirectional,
+        }
+    }
+
+    /// Lobby (1) to Ballroom (2) on one floor, with the Mezzanine (3) above the
+    /// lobby by stairs or a slower elevator, and a Loading Dock (4) linked to nothing.
+    fn venue() -> Vec<LinkRow> {
+        vec![
+            link(1, 2, LinkKind::Hallway, 60, true, true),
+            link(1, 3, LinkKind::Stairs, 30, false, true),
+            link(1, 3, LinkKind::Elevator, 90, true, true),
+            link(3, 2, LinkKind::Escalator, 20, false, false),
+        ]
+    }
+
+    fn ids(locations: &[u128]) -> Vec<LocationId> {
+        locations.iter().map(|&n| location(n).into()).collect()
+    }
+
+    #[test]
+    fn takes_the_shortest_walk() {
+        let routes = WalkGraph::new(&venue(), false).routes_to(location(3).into());
+        let route = routes.route_from(location(2).into()).unwrap();
+        assert_eq!(route.location_ids, ids(&[2, 1, 3]));
+        assert_eq!(route.eta_secs, 90);
+    }
+
+    #[test]
+    fn one_way_links_are_only_walked_forwards() {
+        let graph = WalkGraph::new(&venue(), false);
+        let down = graph.routes_to(location(2).into()).route_from(location(3).into()).unwrap();
+        assert_eq!(down.location_ids, ids(&[3, 2]));
+        assert_eq!(down.eta_secs, 20);
+    }
+
+    #[test]
+    fn accessible_routes_use_the_elevator() {
+        let graph = WalkGraph::new(&venue(), true);
+        let up = graph.routes_to(location(3).into()).route_from(location(1).into()).unwrap();
+        assert_eq!(up.location_ids, ids(&[1, 3]));
+        assert_eq!(up.eta_secs, 90);
+
+        // Without the escalator the way down goes back through the lobby
+        let down = graph.routes_to(location(2).into()).route_from(location(3).into()).unwrap();
+        assert_eq!(down.location_ids, ids(&[3, 1, 2]));
+        assert_eq!(down.eta_secs, 150);
+    }
+
+    #[test]
+    fn disconnected_locations_have_no_route() {
+        let graph = WalkGraph::new(&venue(), false);
+        assert!(graph.routes_to(location(2).into()).route_from(location(4).into()).is_none());
+        assert!(graph.routes_to(location(4).into()).route_from(location(1).into()).is_none());
+    }
+
+    #[test]
+    fn a_location_routes_to_itself() {
+        let routes = WalkGraph::new(&venue(), false).routes_to(location(4).into());
+        let route = routes.route_from(location(4).into()).unwrap();
+        assert_eq!(route.location_ids, ids(&[4]));
+        assert_eq!(route.eta_secs, 0);
+    }
+}
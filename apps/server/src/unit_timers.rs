@@ -0,0 +1,101 @@
+use std::{sync::Arc, time::Duration};
+
+use shared_types::id::{EventId, UnitId};
+use shared_types::{incident::UnitStatus, ProtocolMessage};
+
+use crate::AppState;
+
+/// How long a unit may stay in a status without an update or check-in before
+/// dispatchers are alerted. Statuses without a threshold are never timed.
+#[derive(Debug, Clone)]
+pub struct StatusTimerConfig {
+    pub dispatched: Option<Duration>,
+    pub on_scene: Option<Duration>,
+    pub check_interval: Duration,
+}
+
+impl StatusTimerConfig {
+    /// Reads `UNIT_TIMER_{DISPATCHED,ON_SCENE}_SECS` (0 disables the timer) and
+    /// `UNIT_TIMER_CHECK_INTERVAL_SECS` (at least 1), falling back to 10 and 20
+    /// minutes checked every 15 seconds.
+    pub fn from_env() -> Self {
+        fn secs(var: &str, default: u64) -> u64 {
+            dotenvy::var(var)
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(default)
+        }
+        fn threshold(var: &str, default: u64) -> Option<Duration> {
+            Some(secs(var, default))
+                .filter(|secs| *secs > 0)
+                .map(Duration::from_secs)
+        }
+
+        Self {
+            dispatched: threshold("UNIT_TIMER_DISPATCHED_SECS", 600),
+            on_scene: threshold("UNIT_TIMER_ON_SCENE_SECS", 1200),
+            // A zero interval would panic the timer task
+            check_interval: Duration::from_secs(secs("UNIT_TIMER_CHECK_INTERVAL_SECS", 15).max(1)),
+        }
+    }
+}
+
+struct OverdueUnit {
+    id: UnitId,
+    event_id: EventId,
+    status: UnitStatus,
+    elapsed_secs: i64,
+}
+
+impl AppState {
+    /// Flags units that have gone past their status threshold since their last
+    /// status change or check-in. Each unit is only flagged once per timer.
+    async fn flag_overdue_units(&self) -> Result<Vec<OverdueUnit>, sqlx::Error> {
+        let config = &self.unit_timer_config;
+        let units = sqlx::query_as!(
+            OverdueUnit,
+            r#"
+            UPDATE units
+            SET overdue_alerted_at = NOW()
+            WHERE overdue_alerted_at IS NULL
+                AND GREATEST(status_since, last_check_in) < NOW() - INTERVAL '1 second' * CASE status
+                    WHEN 'dispatched' THEN $1::FLOAT8
+                    WHEN 'on_scene' THEN $2::FLOAT8
+                END
+            RETURNING id as "id: UnitId", event_id as "event_id: EventId", status as "status: UnitStatus",
+                EXTRACT(EPOCH FROM NOW() - GREATEST(status_since, last_check_in))::BIGINT as "elapsed_secs!"
+            "#,
+            config.dispatched.map(|d| d.as_secs_f64()),
+            config.on_scene.map(|d| d.as_secs_f64()),
+        )
+        .fetch_all(&self.db)
+        .await?;
+
+        Ok(units)
+    }
+}
+
+/// Periodically checks unit status timers and broadcasts a `UnitOverdue` alert
+/// for each unit that has gone too long without an update.
+pub async fn run_status_timers(state: Arc<AppState>) {
+    let mut interval = tokio::time::interval(state.unit_timer_config.check_interval);
+    loop {
+        interval.tick().await;
+        match state.flag_overdue_units().await {
+            Ok(units) => {
+                for unit in units {
+                    tracing::warn!("Unit {} overdue in {:?} after {}s", unit.id, unit.status, unit.elapsed_secs);
+                    state.broadcast(
+                        Some(unit.event_id),
+                        ProtocolMessage::UnitOverdue {
+                            unit_id: unit.id,
+                            status: unit.status,
+                            elapsed_secs: unit.elapsed_secs,
+                        },
+                    );
+                }
+            }
+            Err(e) => tracing::error!("Failed to check unit status timers: {}", e),
+        }
+    }
+}
@@ -0,0 +1,262 @@
+use shared_types::id::{EventId, IncidentId, LocationId, UnitId};
+use shared_types::incident::{AssignedUnit, Unit, UnitStatus, UnitType};
+use shared_types::map::Position;
+use sqlx::types::{time::OffsetDateTime, Uuid};
+
+use crate::map::position;
+use crate::AppState;
+
+struct UnitRow {
+    id: Uuid,
+    event_id: Uuid,
+    name: String,
+    unit_type: UnitType,
+    status: UnitStatus,
+    status_since: OffsetDateTime,
+    last_check_in: Option<OffsetDateTime>,
+    last_known_location: Option<String>,
+    emergency_since: Option<OffsetDateTime>,
+    last_longitude: Option<f64>,
+    last_latitude: Option<f64>,
+    location_id: Option<Uuid>,
+}
+
+impl From<UnitRow> for Unit {
+    fn from(row: UnitRow) -> Self {
+        Unit {
+            id: row.id.into(),
+            event_id: row.event_id.into(),
+            name: row.name,
+            unit_type: row.unit_type,
+            status: row.status,
+            status_since: row.status_since,
+            last_check_in: row.last_check_in,
+            last_known_location: row.last_known_location,
+            location_id: row.location_id.map(Into::into),
+            last_position: position(row.last_longitude, row.last_latitude),
+            emergency_since: row.emergency_since,
+        }
+    }
+}
+
+impl AppState {
+    pub async fn list_units(&self, event_id: EventId) -> Result<Vec<Unit>, sqlx::Error> {
+        let units = sqlx::query_as!(
+            UnitRow,
+            r#"
+            SELECT id, event_id, name, unit_type as "unit_type: UnitType", status as "status: UnitStatus",
+                status_since, last_check_in, last_known_location, emergency_since,
+                last_longitude, last_latitude, location_id
+            FROM units
+            WHERE event_id = $1
+            ORDER BY name
+            "#,
+            event_id as EventId,
+        )
+        .fetch_all(&self.db)
+        .await?;
+
+        Ok(units.into_iter().map(Unit::from).collect())
+    }
+
+    pub async fn get_unit(&self, event_id: EventId, unit_id: UnitId) -> Result<Option<Unit>, sqlx::Error> {
+        let unit = sqlx::query_as!(
+            UnitRow,
+            r#"
+            SELECT id, event_id, name, unit_type as "unit_type: UnitType", status as "status: UnitStatus",
+                status_since, last_check_in, last_known_location, emergency_since,
+                last_longitude, last_latitude, location_id
+            FROM units
+            WHERE id = $1 AND event_id = $2
+            "#,
+            unit_id as UnitId,
+            event_id as EventId,
+        )
+        .fetch_optional(&self.db)
+        .await?;
+
+        Ok(unit.map(Unit::from))
+    }
+
+    /// Changes a unit's status, restarting its status timer. A reported location
+    /// replaces the unit's last known location.
+    pub async fn set_unit_status(
+        &self,
+        event_id: EventId,
+        unit_id: UnitId,
+        status: UnitStatus,
+        location: Option<&str>,
+    ) -> Result<Option<Unit>, sqlx::Error> {
+        let unit = sqlx::query_as!(
+            UnitRow,
+            r#"
+            UPDATE units
+            SET status = $1, status_since = NOW(), overdue_alerted_at = NULL,
+                last_known_location = COALESCE($3, last_known_location)
+            WHERE id = $2 AND event_id = $4
+            RETURNING id, event_id, name, unit_type as "unit_type: UnitType", status as "status: UnitStatus",
+                status_since, last_check_in, last_known_location, emergency_since,
+                last_longitude, last_latitude, location_id
+            "#,
+            status as UnitStatus,
+            unit_id as UnitId,
+            location,
+            event_id as EventId,
+        )
+        .fetch_optional(&self.db)
+        .await?;
+
+        Ok(unit.map(Unit::from))
+    }
+
+    /// Records a welfare check-in for a unit, restarting its status timer.
+    pub async fn check_in_unit(&self, event_id: EventId, unit_id: UnitId) -> Result<Option<Unit>, sqlx::Error> {
+        let unit = sqlx::query_as!(
+            UnitRow,
+            r#"
+            UPDATE units
+            SET last_check_in = NOW(), overdue_alerted_at = NULL
+            WHERE id = $1 AND event_id = $2
+            RETURNING id, event_id, name, unit_type as "unit_type: UnitType", status as "status: UnitStatus",
+                status_since, last_check_in, last_known_location, emergency_since,
+                last_longitude, last_latitude, location_id
+            "#,
+            unit_id as UnitId,
+            event_id as EventId,
+        )
+        .fetch_optional(&self.db)
+        .await?;
+
+        Ok(unit.map(Unit::from))
+    }
+
+    pub async fn update_unit_position(&self, event_id: EventId, unit_id: UnitId, position: Position) -> Result<Option<Unit>, sqlx::Error> {
+        let unit = sqlx::query_as!(
+            UnitRow,
+            r#"
+            UPDATE units
+            SET last_longitude = $2, last_latitude = $3, last_position_at = NOW()
+            WHERE id = $1 AND event_id = $4
+            RETURNING id, event_id, name, unit_type as "unit_type: UnitType", status as "status: UnitStatus",
+                status_since, last_check_in, last_known_location, emergency_since,
+                last_longitude, last_latitude, location_id
+            "#,
+            unit_id as UnitId,
+            position.longitude,
+            position.latitude,
+            event_id as EventId,
+        )
+        .fetch_optional(&self.db)
+        .await?;
+
+        Ok(unit.map(Unit::from))
+    }
+
+    pub async fn set_unit_location(&self, event_id: EventId, unit_id: UnitId, location_id: LocationId) -> Result<Option<Unit>, sqlx::Error> {
+        let unit = sqlx::query_as!(
+            UnitRow,
+            r#"
+            UPDATE units u
+            SET location_id = l.id, last_known_location = l.name
+            FROM locations l
+            WHERE u.id = $1 AND l.id = $2 AND u.event_id = $3 AND l.event_id = u.event_id
+            RETURNING u.id, u.event_id, u.name, u.unit_type as "unit_type: UnitType", u.status as "status: UnitStatus",
+                u.status_since, u.last_check_in, u.last_known_location, u.emergency_since,
+                u.last_longitude, u.last_latitude, u.location_id
+            "#,
+            unit_id as UnitId,
+            location_id as LocationId,
+            event_id as EventId,
+        )
+        .fetch_optional(&self.db)
+        .await?;
+
+        Ok(unit.map(Unit::from))
+    }
+
+    /// Records a unit's assignment to an incident and marks it dispatched. The
+    /// incident leaves the pending queue on its first assignment.
+    pub async fn assign_unit(&self, incident_id: IncidentId, unit_id: UnitId, eta_secs: Option<u32>) -> Result<(), sqlx::Error> {
+        let mut tx = self.db.begin().await?;
+
+        sqlx::query!(
+            r#"
+            INSERT INTO incident_units (incident_id, unit_id, eta_secs)
+            VALUES ($1, $2, $3)
+            ON CONFLICT (incident_id, unit_id) DO UPDATE
+            SET assigned_at = NOW(), eta_secs = EXCLUDED.eta_secs
+            "#,
+            incident_id as IncidentId,
+            unit_id as UnitId,
+            eta_secs.map(|secs| secs as i32),
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        sqlx::query!(
+            r#"
+            UPDATE units
+            SET status = 'dispatched', status_since = NOW(), overdue_alerted_at = NULL
+            WHERE id = $1
+            "#,
+            unit_id as UnitId,
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        sqlx::query!(
+            r#"
+            UPDATE incidents
+            SET assigned_at = COALESCE(assigned_at, NOW())
+            WHERE id = $1
+            "#,
+            incident_id as IncidentId,
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+        Ok(())
+    }
+
+    pub async fn units_for_incident(&self, incident_id: IncidentId) -> Result<Vec<AssignedUnit>, sqlx::Error> {
+        let rows = sqlx::query!(
+            r#"
+            SELECT u.id, u.event_id, u.name, u.unit_type as "unit_type: UnitType", u.status as "status: UnitStatus",
+                u.status_since, u.last_check_in, u.last_known_location, u.emergency_since,
+                u.last_longitude, u.last_latitude, u.location_id,
+                iu.assigned_at, iu.eta_secs
+            FROM incident_units iu
+            JOIN units u ON u.id = iu.unit_id
+            WHERE iu.incident_id = $1
+            ORDER BY iu.assigned_at
+            "#,
+            incident_id as IncidentId,
+        )
+        .fetch_all(&self.db)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| AssignedUnit {
+                unit: UnitRow {
+                    id: row.id,
+                    event_id: row.event_id,
+                    name: row.name,
+                    unit_type: row.unit_type,
+                    status: row.status,
+                    status_since: row.status_since,
+                    last_check_in: row.last_check_in,
+                    last_known_location: row.last_known_location,
+                    emergency_since: row.emergency_since,
+                    last_longitude: row.last_longitude,
+                    last_latitude: row.last_latitude,
+                    location_id: row.location_id,
+                }
+                .into(),
+                assigned_at: row.assigned_at,
+                eta_secs: row.eta_secs.map(|secs| secs as u32),
+            })
+            .collect())
+    }
+}
@@ -1,12 +1,13 @@
 use sqlx::{ types::Uuid, types::time::OffsetDateTime};
 use serde::{Deserialize, Serialize};
+use shared_types::id::UserId;
 use time::serde::timestamp;
 
 use crate::AppState;
 use crate::auth::CloudflareJWTClaims; // Import the CloudflareJwtClaims type
 
 #[derive(Debug, Serialize, Deserialize, sqlx::Type, Clone, PartialEq)]
-#[sqlx(type_name = "user_role", rename_all = "lowercase")]
+#[sqlx(type_name = "user_role", rename_all = "SCREAMING_SNAKE_CASE")]
 pub enum UserRole {
     CadUser,
     CadManager,
@@ -15,8 +16,7 @@ pub enum UserRole {
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct User {
-    #[serde(with = "uuid_serialization")]
-    pub id: Uuid,
+    pub id: UserId,
     pub email: String,
     pub name: Option<String>,
     pub roles: Vec<UserRole>,
@@ -35,41 +35,23 @@ impl User {
     pub fn is_admin(&self) -> bool {
         self.has_role(&UserRole::CadAdmin)
     }
+
+    /// Managers and admins act as dispatch supervisors.
+    pub fn is_supervisor(&self) -> bool {
+        self.is_admin() || self.has_role(&UserRole::CadManager)
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct UserSession {
-    #[serde(with = "uuid_serialization")]
     pub id: Uuid,
-    #[serde(with = "uuid_serialization")]
-    pub user_id: Uuid,
+    pub user_id: UserId,
     #[serde(with = "timestamp")]
     pub last_ping: OffsetDateTime,
     #[serde(with = "timestamp")]
     pub created_at: OffsetDateTime,
 }
 
-// Custom UUID serialization module
-mod uuid_serialization {
-    use serde::{self, Deserialize, Deserializer, Serializer};
-    use sqlx::types::Uuid;
-
-    pub fn serialize<S>(uuid: &Uuid, serializer: S) -> Result<S::Ok, S::Error>
-    where
-        S: Serializer,
-    {
-        serializer.serialize_str(&uuid.to_string())
-    }
-
-    pub fn deserialize<'de, D>(deserializer: D) -> Result<Uuid, D::Error>
-    where
-        D: Deserializer<'de>,
-    {
-        let s = String::deserialize(deserializer)?;
-        Uuid::parse_str(&s).map_err(serde::de::Error::custom)
-    }
-}
-
 impl AppState {
     // Get or create user based on Cloudflare claims
     pub async fn get_or_create_user(&self, claims: &CloudflareJWTClaims) -> Result<User, sqlx::Error> {
@@ -83,7 +65,7 @@ impl AppState {
             VALUES ($1, $2, $3)
             ON CONFLICT (email) DO UPDATE
             SET last_login = NOW()
-            RETURNING id, email, name, roles as "roles: Vec<UserRole>", created_at, last_login, is_active
+            RETURNING id as "id: UserId", email, name, roles as "roles: Vec<UserRole>", created_at, last_login, is_active
             "#,
             claims.email,
             claims.name,
@@ -95,17 +77,17 @@ impl AppState {
         Ok(user)
     }
 
-    pub async fn update_user_roles(&self, user_id: Uuid, roles: &[UserRole]) -> Result<User, sqlx::Error> {
+    pub async fn update_user_roles(&self, user_id: UserId, roles: &[UserRole]) -> Result<User, sqlx::Error> {
         let user = sqlx::query_as!(
             User,
             r#"
             UPDATE users
             SET roles = $1
             WHERE id = $2
-            RETURNING id, email, name, roles as "roles: Vec<UserRole>", created_at, last_login, is_active
+            RETURNING id as "id: UserId", email, name, roles as "roles: Vec<UserRole>", created_at, last_login, is_active
             "#,
             roles as &[UserRole],
-            user_id,
+            user_id as UserId,
         )
         .fetch_one(&self.db)
         .await?;
@@ -113,17 +95,17 @@ impl AppState {
         Ok(user)
     }
 
-    pub async fn add_user_role(&self, user_id: Uuid, role: UserRole) -> Result<User, sqlx::Error> {
+    pub async fn add_user_role(&self, user_id: UserId, role: UserRole) -> Result<User, sqlx::Error> {
         let user = sqlx::query_as!(
             User,
             r#"
             UPDATE users
             SET roles = array_append(roles, $1)
             WHERE id = $2 AND NOT $1 = ANY(roles)
-            RETURNING id, email, name, roles as "roles: Vec<UserRole>", created_at, last_login, is_active
+            RETURNING id as "id: UserId", email, name, roles as "roles: Vec<UserRole>", created_at, last_login, is_active
             "#,
             role as UserRole,
-            user_id,
+            user_id as UserId,
         )
         .fetch_one(&self.db)
         .await?;
@@ -131,17 +113,17 @@ impl AppState {
         Ok(user)
     }
 
-    pub async fn remove_user_role(&self, user_id: Uuid, role: UserRole) -> Result<User, sqlx::Error> {
+    pub async fn remove_user_role(&self, user_id: UserId, role: UserRole) -> Result<User, sqlx::Error> {
         let user = sqlx::query_as!(
             User,
             r#"
             UPDATE users
             SET roles = array_remove(roles, $1)
             WHERE id = $2
-            RETURNING id, email, name, roles as "roles: Vec<UserRole>", created_at, last_login, is_active
+            RETURNING id as "id: UserId", email, name, roles as "roles: Vec<UserRole>", created_at, last_login, is_active
             "#,
             role as UserRole,
-            user_id,
+            user_id as UserId,
         )
         .fetch_one(&self.db)
         .await?;
@@ -149,15 +131,15 @@ impl AppState {
         Ok(user)
     }
 
-    pub async fn create_session(&self, user_id: Uuid) -> Result<UserSession, sqlx::Error> {
+    pub async fn create_session(&self, user_id: UserId) -> Result<UserSession, sqlx::Error> {
         let session = sqlx::query_as!(
             UserSession,
             r#"
             INSERT INTO user_sessions (user_id)
             VALUES ($1)
-            RETURNING id, user_id, last_ping, created_at
+            RETURNING id, user_id as "user_id: UserId", last_ping, created_at
             "#,
-            user_id,
+            user_id as UserId,
         )
         .fetch_one(&self.db)
         .await?;
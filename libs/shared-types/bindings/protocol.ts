@@ -0,0 +1,171 @@
+// Generated from libs/shared-types. Do not edit by hand.
+
+export type AssignedUnit = { unit: Unit, assigned_at: string, 
+/**
+ * Estimated walking time from where the unit was when dispatched
+ */
+eta_secs: number | null, };
+
+export type CallNature = { code: string, description: string, default_priority: Priority, default_incident_type: IncidentType, recommended_unit_types: Array<UnitType>, };
+
+export type CallUpdate = { name: string | null, location_id?: LocationId | null, location_detail?: string | null, phone_number: string | null, caller_name: string | null, incident_type: IncidentType | null, priority: Priority | null, disposition: Disposition | null, };
+
+export type Disposition = "resolved" | "unresolved" | "pending";
+
+export type Envelope = { request_id?: number, } & ProtocolMessage;
+
+export type Event = { id: EventId, name: string, starts_on: string, ends_on: string, 
+/**
+ * IANA time zone the event runs in, e.g. "America/Chicago"
+ */
+time_zone: string, settings: JsonValue, };
+
+export type EventId = string;
+
+export type IncidentCall = { id: IncidentId, incident_number: string, event_id: EventId, date_of_service: string, name: string, location: IncidentLocation, dob: string | null, badge_number: string | null, phone_number: string, caller_name: string, incident_type: IncidentType, priority: Priority, call_nature: CallNature, notes: Array<Note>, disposition: Disposition, units_assigned: Array<AssignedUnit>, times: IncidentTimes, };
+
+export type IncidentId = string;
+
+export type IncidentLocation = { location_id: LocationId | null, 
+/**
+ * Name of the registered location, filled in by the server
+ */
+name: string | null, 
+/**
+ * Free-text detail such as "by the east doors"
+ */
+detail: string | null, };
+
+export type IncidentTimes = { received: string, assigned: string | null, responding: string | null, on_scene: string | null, transporting: string | null, at_destination: string | null, cleared: string | null, 
+/**
+ * When the call was escalated for waiting too long in the pending queue
+ */
+escalated: string | null, };
+
+export type IncidentType = "security" | "medical";
+
+export type JsonValue = number | string | boolean | Array<JsonValue> | { [key in string]?: JsonValue } | null;
+
+export type LinkKind = "hallway" | "elevator" | "stairs" | "escalator" | "outdoor";
+
+export type Location = { id: LocationId, event_id: EventId, name: string, kind: LocationKind, 
+/**
+ * The building or floor this location sits within
+ */
+parent_id: LocationId | null, 
+/**
+ * Other names dispatchers use for the same place, e.g. "BR-B"
+ */
+aliases: Array<string>, position: Position | null, };
+
+export type LocationId = string;
+
+export type LocationKind = "building" | "floor" | "room" | "outdoor_zone" | "post";
+
+export type LocationLink = { id: LocationLinkId, event_id: EventId, from_location_id: LocationId, to_location_id: LocationId, kind: LinkKind, 
+/**
+ * Typical walking time in seconds
+ */
+travel_secs: number, 
+/**
+ * Usable with a wheelchair or stretcher
+ */
+accessible: boolean, 
+/**
+ * `false` for one-way links such as escalators
+ */
+bidirectional: boolean, };
+
+export type LocationLinkId = string;
+
+export type MapLayer = { name: string, kind: MapLayerKind, geojson: JsonValue, };
+
+export type MapLayerKind = "venue" | "units" | "incidents";
+
+export type NewEvent = { name: string, starts_on: string, ends_on: string, time_zone: string, settings: JsonValue, };
+
+export type NewIncidentCall = { name: string, location_id: LocationId | null, location_detail: string | null, dob: string | null, badge_number: string | null, phone_number: string, caller_name: string, nature_code: string, incident_type: IncidentType | null, priority: Priority | null, };
+
+export type NewLocation = { name: string, kind: LocationKind, parent_id: LocationId | null, aliases: Array<string>, };
+
+export type NewLocationLink = { from_location_id: LocationId, to_location_id: LocationId, kind: LinkKind, travel_secs: number, accessible: boolean, bidirectional: boolean, };
+
+export type Note = { id: NoteId, author: string, content: string, timestamp: string, };
+
+export type NoteId = string;
+
+export type OnlineDispatcher = { user_id: UserId, 
+/**
+ * Display name, or email for users without one
+ */
+name: string, 
+/**
+ * Consoles they have open on the event
+ */
+consoles: number, };
+
+export type Position = { longitude: number, latitude: number, };
+
+export type Priority = "high" | "medium" | "low";
+
+export type ProtocolMessage = { "type": "hello", "payload": { 
+/**
+ * Oldest and newest protocol versions the client can speak
+ */
+min_version: number, max_version: number, 
+/**
+ * Client name and version, for the server's logs
+ */
+client: string, 
+/**
+ * Optional features the client would like enabled
+ */
+capabilities: Array<string>, } } | { "type": "welcome", "payload": { 
+/**
+ * Version both sides will speak for the rest of the connection
+ */
+version: number, server: string, 
+/**
+ * Requested capabilities the server has enabled
+ */
+capabilities: Array<string>, } } | { "type": "unsupported_version", "payload": { min_version: number, max_version: number, 
+/**
+ * Human-readable explanation telling the user what to upgrade
+ */
+message: string, } } | { "type": "ping" } | { "type": "list_events" } | { "type": "events", "payload": { events: Array<Event>, } } | { "type": "create_event", "payload": { event: NewEvent, } } | { "type": "event", "payload": { event: Event, } } | { "type": "set_active_event", "payload": { event_id: EventId, } } | { "type": "active_event", "payload": { event: Event, } } | { "type": "get_active_calls" } | { "type": "active_calls", "payload": { calls: Array<IncidentCall>, } } | { "type": "get_event_calls", "payload": { event_id: EventId, } } | { "type": "event_calls", "payload": { event_id: EventId, calls: Array<IncidentCall>, } } | { "type": "get_calls_on_day", "payload": { date: string, } } | { "type": "calls_on_day", "payload": { date: string, calls: Array<IncidentCall>, } } | { "type": "get_call", "payload": { id: IncidentId, } } | { "type": "create_call", "payload": { call: NewIncidentCall, } } | { "type": "update_call", "payload": { id: IncidentId, update: CallUpdate, } } | { "type": "add_note", "payload": { incident_id: IncidentId, content: string, } } | { "type": "get_call_natures" } | { "type": "call_natures", "payload": { natures: Array<CallNature>, } } | { "type": "call", "payload": { call: IncidentCall, } } | { "type": "get_pending_queue" } | { "type": "pending_queue", "payload": { calls: Array<IncidentCall>, } } | { "type": "call_escalated", "payload": { id: IncidentId, priority: Priority, waiting_secs: number, } } | { "type": "get_units" } | { "type": "units", "payload": { units: Array<Unit>, } } | { "type": "update_unit_status", "payload": { unit_id: UnitId, status: UnitStatus, location: string | null, } } | { "type": "unit_updated", "payload": { unit: Unit, } } | { "type": "check_in_unit", "payload": { unit_id: UnitId, } } | { "type": "unit_overdue", "payload": { unit_id: UnitId, status: UnitStatus, elapsed_secs: number, } } | { "type": "activate_emergency", "payload": { unit_id: UnitId, location: string | null, } } | { "type": "emergency_alert", "payload": { unit: Unit, incident_id: IncidentId, location: string, audible: boolean, } } | { "type": "acknowledge_emergency", "payload": { unit_id: UnitId, } } | { "type": "emergency_acknowledged", "payload": { unit_id: UnitId, acknowledged_by: string, } } | { "type": "clear_emergency", "payload": { unit_id: UnitId, } } | { "type": "emergency_cleared", "payload": { unit_id: UnitId, cleared_by: string, } } | { "type": "start_roll_call", "payload": { reason: string, } } | { "type": "mark_unit_accounted", "payload": { roll_call_id: RollCallId, unit_id: UnitId, } } | { "type": "realert_roll_call", "payload": { roll_call_id: RollCallId, } } | { "type": "complete_roll_call", "payload": { roll_call_id: RollCallId, } } | { "type": "get_roll_calls" } | { "type": "roll_calls", "payload": { roll_calls: Array<RollCall>, } } | { "type": "roll_call_updated", "payload": { roll_call: RollCall, } } | { "type": "roll_call_alert", "payload": { roll_call_id: RollCallId, outstanding: Array<RollCallEntry>, } } | { "type": "get_locations" } | { "type": "locations", "payload": { locations: Array<Location>, } } | { "type": "resolve_location", "payload": { query: string, } } | { "type": "location_matches", "payload": { query: string, matches: Array<Location>, } } | { "type": "create_location", "payload": { location: NewLocation, } } | { "type": "location", "payload": { location: Location, } } | { "type": "import_map_layer", "payload": { name: string, geojson: JsonValue, } } | { "type": "map_layer_imported", "payload": { name: string, located: number, } } | { "type": "set_location_position", "payload": { location_id: LocationId, position: Position, } } | { "type": "update_unit_position", "payload": { unit_id: UnitId, position: Position, } } | { "type": "get_map_layers" } | { "type": "map_layers", "payload": { layers: Array<MapLayer>, } } | { "type": "create_location_link", "payload": { link: NewLocationLink, } } | { "type": "get_location_links" } | { "type": "location_links", "payload": { links: Array<LocationLink>, } } | { "type": "get_route", "payload": { from_location_id: LocationId, to_location_id: LocationId, accessible_only: boolean, } } | { "type": "route", "payload": { route: Route | null, } } | { "type": "update_unit_location", "payload": { unit_id: UnitId, location_id: LocationId, } } | { "type": "recommend_units", "payload": { incident_id: IncidentId, accessible_only: boolean, } } | { "type": "unit_recommendations", "payload": { incident_id: IncidentId, recommendations: Array<UnitRecommendation>, } } | { "type": "dispatch_unit", "payload": { incident_id: IncidentId, unit_id: UnitId, } } | { "type": "call_updated", "payload": { call: IncidentCall, } } | { "type": "presence_changed", "payload": { event_id: EventId, online: Array<OnlineDispatcher>, } } | { "type": "ack" } | { "type": "error", "payload": { message: string, } } | { "type": "text", "payload": string } | { "type": "json", "payload": string };
+
+export type RollCall = { id: RollCallId, event_id: EventId, reason: string, started_by: string, started_at: string, completed_at: string | null, entries: Array<RollCallEntry>, };
+
+export type RollCallEntry = { unit_id: UnitId, unit_name: string, accounted_at: string | null, accounted_by: string | null, };
+
+export type RollCallId = string;
+
+export type Route = { 
+/**
+ * Locations visited in order, including both ends
+ */
+location_ids: Array<LocationId>, eta_secs: number, };
+
+export type Unit = { id: UnitId, event_id: EventId, name: string, unit_type: UnitType, status: UnitStatus, status_since: string, last_check_in: string | null, last_known_location: string | null, 
+/**
+ * Registered location the unit is at, used for routing
+ */
+location_id: LocationId | null, last_position: Position | null, 
+/**
+ * Set while the unit has an active emergency that has not been cleared
+ */
+emergency_since: string | null, };
+
+export type UnitId = string;
+
+export type UnitRecommendation = { unit: Unit, 
+/**
+ * `None` when the unit's location is unknown or unreachable
+ */
+route: Route | null, };
+
+export type UnitStatus = "available" | "dispatched" | "on_scene" | "unavailable";
+
+export type UnitType = "security" | "first_aid";
+
+export type UserId = string;
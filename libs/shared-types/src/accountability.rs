@@ -0,0 +1,48 @@
+#[cfg(feature = "codegen")]
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "codegen")]
+use ts_rs::TS;
+use time::OffsetDateTime;
+
+use crate::id::{EventId, RollCallId, UnitId};
+
+/// A personnel accountability check started by a supervisor.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[cfg_attr(feature = "codegen", derive(TS, JsonSchema))]
+pub struct RollCall {
+    pub id: RollCallId,
+    pub event_id: EventId,
+    pub reason: String,
+    pub started_by: String,
+    #[serde(with = "crate::wire::timestamp")]
+    #[cfg_attr(feature = "codegen", ts(as = "String"), schemars(with = "String"))]
+    pub started_at: OffsetDateTime,
+    #[serde(default, with = "crate::wire::timestamp::option")]
+    #[cfg_attr(feature = "codegen", ts(as = "Option<String>"), schemars(with = "Option<String>"))]
+    pub completed_at: Option<OffsetDateTime>,
+    pub entries: Vec<RollCallEntry>,
+}
+
+/// A unit that must be accounted for in a roll call.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[cfg_attr(feature = "codegen", derive(TS, JsonSchema))]
+pub struct RollCallEntry {
+    pub unit_id: UnitId,
+    pub unit_name: String,
+    #[serde(default, with = "crate::wire::timestamp::option")]
+    #[cfg_attr(feature = "codegen", ts(as = "Option<String>"), schemars(with = "Option<String>"))]
+    pub accounted_at: Option<OffsetDateTime>,
+    pub accounted_by: Option<String>,
+}
+
+impl RollCall {
+    /// Units not yet accounted for.
+    pub fn outstanding(&self) -> Vec<RollCallEntry> {
+        self.entries
+            .iter()
+            .filter(|entry| entry.accounted_at.is_none())
+            .cloned()
+            .collect()
+    }
+}
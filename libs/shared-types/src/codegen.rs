@@ -0,0 +1,63 @@
+//! TypeScript definitions and JSON Schema for the wire format, generated from
+//! the Rust types so the frontend and other integrations never drift from them.
+//!
+//! The checked-in copies live in `bindings/`; `tests/bindings.rs` fails when
+//! they are out of date. Regenerate with `UPDATE_BINDINGS=1 cargo test -p shared-types`.
+//!
+//! Only built with the `codegen` feature, which the tests turn on, so the
+//! server and client don't compile the generators.
+
+use std::collections::HashSet;
+
+use ts_rs::{TypeVisitor, TS};
+
+use crate::{Envelope, ProtocolMessage};
+
+const HEADER: &str = "// Generated from libs/shared-types. Do not edit by hand.\n";
+
+/// Declaration of [`Envelope`], which ts-rs cannot express without inlining
+/// every `ProtocolMessage` variant.
+const ENVELOPE_DECL: &str = "export type Envelope = { request_id?: number, } & ProtocolMessage;";
+
+/// Collects the declaration of every named type reachable from a root type.
+#[derive(Default)]
+struct Declarations {
+    /// TypeScript names already declared; shadowed types such as
+    /// `serde_json::Value` share a name with the type they stand in for.
+    seen: HashSet<String>,
+    decls: Vec<String>,
+}
+
+impl TypeVisitor for Declarations {
+    fn visit<T: TS + 'static + ?Sized>(&mut self) {
+        if T::output_path().is_none() || !self.seen.insert(T::ident()) {
+            return;
+        }
+        self.decls.push(format!("export {}", T::decl()));
+        T::visit_dependencies(self);
+    }
+}
+
+/// TypeScript declarations for `Envelope`, `ProtocolMessage` and every type
+/// they contain, sorted by name.
+pub fn typescript() -> String {
+    let mut declarations = Declarations::default();
+    declarations.visit::<ProtocolMessage>();
+    declarations.decls.push(ENVELOPE_DECL.to_string());
+    declarations.decls.sort();
+
+    let mut out = HEADER.to_string();
+    for decl in declarations.decls {
+        out.push('\n');
+        out.push_str(&decl);
+        out.push('\n');
+    }
+    out
+}
+
+/// JSON Schema (draft 7) for `Envelope`, with every contained type under
+/// `definitions`.
+pub fn json_schema() -> String {
+    let schema = schemars::schema_for!(Envelope);
+    serde_json::to_string_pretty(&schema).expect("schema serializes") + "\n"
+}
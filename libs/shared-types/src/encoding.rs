@@ -0,0 +1,127 @@
+//! Optional compact encodings for messages (normally an `Envelope`), negotiated
+//! per connection.
+//!
+//! Every connection starts with JSON text frames. A client that lists
+//! [`MSGPACK`] and/or [`DEFLATE`] in its `Hello` capabilities switches to
+//! binary frames once the server's `Welcome` enables them:
+//!
+//! - `msgpack`: the message is MessagePack instead of JSON, with structs encoded
+//!   as maps so field names (and the `type`/`payload` envelope) survive.
+//! - `deflate`: the frame body (JSON or MessagePack) is raw-DEFLATE compressed.
+//!   Neither axum nor tungstenite implement the permessage-deflate extension,
+//!   so compression happens here rather than in the WebSocket layer.
+//!
+//! Text frames are always JSON and are accepted whatever was negotiated, so the
+//! `Hello`/`Welcome` exchange and hand-typed debugging messages keep working.
+
+use std::fmt;
+use std::io::{Read, Write};
+
+use flate2::{read::DeflateDecoder, write::DeflateEncoder, Compression};
+use serde::{de::DeserializeOwned, Serialize};
+
+/// Capability name for MessagePack binary frames.
+pub const MSGPACK: &str = "msgpack";
+/// Capability name for compressed binary frames.
+pub const DEFLATE: &str = "deflate";
+
+/// Largest message body a compressed frame may inflate to. Map layers are the
+/// biggest messages by far; anything past this is refused rather than held in
+/// memory.
+pub const MAX_FRAME_BYTES: u64 = 16 * 1024 * 1024;
+
+/// How messages are framed on a connection.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Encoding {
+    pub msgpack: bool,
+    pub deflate: bool,
+}
+
+/// A serialized message, ready to become a WebSocket text or binary frame.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Frame {
+    Text(String),
+    Binary(Vec<u8>),
+}
+
+#[derive(Debug)]
+pub enum Error {
+    Json(serde_json::Error),
+    MsgpackEncode(rmp_serde::encode::Error),
+    MsgpackDecode(rmp_serde::decode::Error),
+    Deflate(std::io::Error),
+    /// The frame inflated past [`MAX_FRAME_BYTES`]
+    TooLarge,
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Json(e) => write!(f, "JSON: {}", e),
+            Error::MsgpackEncode(e) => write!(f, "MessagePack: {}", e),
+            Error::MsgpackDecode(e) => write!(f, "MessagePack: {}", e),
+            Error::Deflate(e) => write!(f, "deflate: {}", e),
+            Error::TooLarge => write!(f, "frame inflates past {} bytes", MAX_FRAME_BYTES),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl Encoding {
+    /// The encoding enabled by a negotiated capability list; unknown names are ignored.
+    pub fn from_capabilities<S: AsRef<str>>(capabilities: &[S]) -> Self {
+        let has = |name: &str| capabilities.iter().any(|capability| capability.as_ref() == name);
+        Self {
+            msgpack: has(MSGPACK),
+            deflate: has(DEFLATE),
+        }
+    }
+
+    /// Whether messages go out as binary frames.
+    pub fn is_binary(&self) -> bool {
+        self.msgpack || self.deflate
+    }
+
+    pub fn encode<T: Serialize>(&self, message: &T) -> Result<Frame, Error> {
+        if !self.is_binary() {
+            return serde_json::to_string(message).map(Frame::Text).map_err(Error::Json);
+        }
+
+        let body = if self.msgpack {
+            rmp_serde::to_vec_named(message).map_err(Error::MsgpackEncode)?
+        } else {
+            serde_json::to_vec(message).map_err(Error::Json)?
+        };
+        if !self.deflate {
+            return Ok(Frame::Binary(body));
+        }
+
+        let mut encoder = DeflateEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(&body).map_err(Error::Deflate)?;
+        encoder.finish().map(Frame::Binary).map_err(Error::Deflate)
+    }
+
+    /// Decodes a binary frame sent with this encoding.
+    pub fn decode_binary<T: DeserializeOwned>(&self, frame: &[u8]) -> Result<T, Error> {
+        let mut inflated = Vec::new();
+        let body = if self.deflate {
+            DeflateDecoder::new(frame)
+                .take(MAX_FRAME_BYTES + 1)
+                .read_to_end(&mut inflated)
+                .map_err(Error::Deflate)?;
+            if inflated.len() as u64 > MAX_FRAME_BYTES {
+                return Err(Error::TooLarge);
+            }
+            &inflated[..]
+        } else {
+            frame
+        };
+
+        if self.msgpack {
+            rmp_serde::from_slice(body).map_err(Error::MsgpackDecode)
+        } else {
+            serde_json::from_slice(body).map_err(Error::Json)
+        }
+    }
+}
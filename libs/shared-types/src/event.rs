@@ -0,0 +1,99 @@
+#[cfg(feature = "codegen")]
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "codegen")]
+use ts_rs::TS;
+use time::{format_description::well_known::Rfc3339, Date, OffsetDateTime, Time};
+use crate::id::EventId;
+use time_tz::{timezones, Offset, OffsetDateTimeExt, OffsetResult, PrimitiveDateTimeExt, TimeZone, Tz};
+
+/// A convention or other deployment that incidents, units and locations belong to.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[cfg_attr(feature = "codegen", derive(TS, JsonSchema))]
+pub struct Event {
+    pub id: EventId,
+    pub name: String,
+    #[serde(with = "crate::wire::date")]
+    #[cfg_attr(feature = "codegen", ts(as = "String"), schemars(with = "String"))]
+    pub starts_on: Date,
+    #[serde(with = "crate::wire::date")]
+    #[cfg_attr(feature = "codegen", ts(as = "String"), schemars(with = "String"))]
+    pub ends_on: Date,
+    /// IANA time zone the event runs in, e.g. "America/Chicago"
+    pub time_zone: String,
+    pub settings: serde_json::Value,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[cfg_attr(feature = "codegen", derive(TS, JsonSchema))]
+pub struct NewEvent {
+    pub name: String,
+    #[serde(with = "crate::wire::date")]
+    #[cfg_attr(feature = "codegen", ts(as = "String"), schemars(with = "String"))]
+    pub starts_on: Date,
+    #[serde(with = "crate::wire::date")]
+    #[cfg_attr(feature = "codegen", ts(as = "String"), schemars(with = "String"))]
+    pub ends_on: Date,
+    pub time_zone: String,
+    pub settings: serde_json::Value,
+}
+
+/// Whether `name` is a time zone in the bundled IANA database.
+pub fn is_valid_time_zone(name: &str) -> bool {
+    timezones::get_by_name(name).is_some()
+}
+
+impl Event {
+    /// The event's time zone, falling back to UTC if the stored name is unknown.
+    fn tz(&self) -> &'static Tz {
+        timezones::get_by_name(&self.time_zone).unwrap_or(timezones::db::UTC)
+    }
+
+    /// Converts an instant to the event's wall-clock time, with the offset in
+    /// effect at that instant (so DST changes are respected).
+    pub fn local_time(&self, at: OffsetDateTime) -> OffsetDateTime {
+        at.to_timezone(self.tz())
+    }
+
+    /// The event-local calendar day an instant falls on.
+    pub fn local_date(&self, at: OffsetDateTime) -> Date {
+        self.local_time(at).date()
+    }
+
+    /// Whether `at` falls within the event's dates in event-local time.
+    pub fn is_running_at(&self, at: OffsetDateTime) -> bool {
+        (self.starts_on..=self.ends_on).contains(&self.local_date(at))
+    }
+
+    /// The instant event-local `date` begins. When midnight falls in a DST gap
+    /// the day begins at the first valid instant after it.
+    pub fn start_of_day(&self, date: Date) -> OffsetDateTime {
+        let midnight = date.midnight();
+        match midnight.assume_timezone(self.tz()) {
+            OffsetResult::Some(start) => start,
+            OffsetResult::Ambiguous(earlier, _) => earlier,
+            OffsetResult::None => midnight.assume_timezone_utc(self.tz()),
+        }
+    }
+
+    /// The half-open range `[start, end)` of instants that fall on event-local
+    /// `date`. Days containing a DST change are 23 or 25 hours long.
+    pub fn day_bounds(&self, date: Date) -> (OffsetDateTime, OffsetDateTime) {
+        let end = match date.next_day() {
+            Some(next) => self.start_of_day(next),
+            None => OffsetDateTime::new_utc(Date::MAX, Time::from_hms_nano(23, 59, 59, 999_999_999).unwrap()),
+        };
+        (self.start_of_day(date), end)
+    }
+
+    /// Renders an instant in event-local time for reports and displays, e.g.
+    /// "2024-11-03T01:30:00-05:00 CDT".
+    pub fn format_local(&self, at: OffsetDateTime) -> String {
+        let local = self.local_time(at);
+        let abbreviation = self.tz().get_offset_utc(&at);
+        match local.format(&Rfc3339) {
+            Ok(formatted) => format!("{} {}", formatted, abbreviation.name()),
+            Err(_) => local.to_string(),
+        }
+    }
+}
@@ -0,0 +1,109 @@
+//! Strongly typed identifiers.
+//!
+//! Every ID is a UUID on the wire (a hyphenated string in every encoding) and
+//! in the database, but each kind of record gets its own type so a unit ID
+//! cannot be passed where an incident ID is expected. Parsing validates the
+//! UUID, so a malformed ID is rejected when a message is decoded rather than
+//! deep inside a handler.
+
+use std::fmt;
+use std::str::FromStr;
+
+#[cfg(feature = "codegen")]
+use schemars::JsonSchema;
+use serde::{de::Error as _, Deserialize, Deserializer, Serialize, Serializer};
+#[cfg(feature = "codegen")]
+use ts_rs::TS;
+use uuid::Uuid;
+
+/// A string that is not a valid ID of the expected kind.
+#[derive(Debug, Clone)]
+pub struct ParseIdError {
+    kind: &'static str,
+    source: uuid::Error,
+}
+
+impl fmt::Display for ParseIdError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid {} ID: {}", self.kind, self.source)
+    }
+}
+
+impl std::error::Error for ParseIdError {}
+
+macro_rules! id {
+    ($(#[$meta:meta])* $name:ident, $kind:literal) => {
+        $(#[$meta])*
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+        #[cfg_attr(feature = "codegen", derive(TS, JsonSchema))]
+        #[cfg_attr(feature = "sqlx", derive(sqlx::Type), sqlx(transparent))]
+        pub struct $name(
+            #[cfg_attr(feature = "codegen", ts(as = "String"), schemars(with = "String"))]
+            Uuid,
+        );
+
+        impl $name {
+            pub fn as_uuid(&self) -> Uuid {
+                self.0
+            }
+        }
+
+        impl From<Uuid> for $name {
+            fn from(uuid: Uuid) -> Self {
+                Self(uuid)
+            }
+        }
+
+        impl From<$name> for Uuid {
+            fn from(id: $name) -> Self {
+                id.0
+            }
+        }
+
+        impl fmt::Display for $name {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                self.0.hyphenated().fmt(f)
+            }
+        }
+
+        impl FromStr for $name {
+            type Err = ParseIdError;
+
+            fn from_str(s: &str) -> Result<Self, Self::Err> {
+                Uuid::parse_str(s)
+                    .map(Self)
+                    .map_err(|source| ParseIdError { kind: $kind, source })
+            }
+        }
+
+        impl Serialize for $name {
+            fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                serializer.collect_str(self)
+            }
+        }
+
+        impl<'de> Deserialize<'de> for $name {
+            fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                let s = String::deserialize(deserializer)?;
+                s.parse().map_err(D::Error::custom)
+            }
+        }
+    };
+}
+
+id!(
+    /// A convention or other deployment.
+    EventId,
+    "event"
+);
+id!(IncidentId, "incident");
+id!(UnitId, "unit");
+id!(NoteId, "note");
+id!(
+    /// A dispatcher or other console user.
+    UserId,
+    "user"
+);
+id!(LocationId, "location");
+id!(LocationLinkId, "location link");
+id!(RollCallId, "roll call");
@@ -1,47 +1,129 @@
+#[cfg(feature = "codegen")]
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
+#[cfg(feature = "codegen")]
+use ts_rs::TS;
 use time::OffsetDateTime;
 
+use crate::id::{EventId, IncidentId, LocationId, NoteId, UnitId};
+use crate::location::IncidentLocation;
+use crate::map::Position;
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
+#[cfg_attr(feature = "codegen", derive(TS, JsonSchema))]
 pub struct IncidentCall {
+    pub id: IncidentId,
     pub incident_number: String,
-    pub event: String,
+    pub event_id: EventId,
+    #[serde(with = "crate::wire::timestamp")]
+    #[cfg_attr(feature = "codegen", ts(as = "String"), schemars(with = "String"))]
     pub date_of_service: OffsetDateTime,
     pub name: String,
-    pub location: String,
+    pub location: IncidentLocation,
+    #[serde(default, with = "crate::wire::timestamp::option")]
+    #[cfg_attr(feature = "codegen", ts(as = "Option<String>"), schemars(with = "Option<String>"))]
     pub dob: Option<OffsetDateTime>,
     pub badge_number: Option<String>,
     pub phone_number: String,
     pub caller_name: String,
     pub incident_type: IncidentType,
+    pub priority: Priority,
     pub call_nature: CallNature,
     pub notes: Vec<Note>,
     pub disposition: Disposition,
-    pub units_assigned: Vec<Unit>,
+    pub units_assigned: Vec<AssignedUnit>,
     pub times: IncidentTimes,
 }
 
-
+/// Fields supplied by a dispatcher when opening a new call in their active event.
+///
+/// Anything left as `None` is filled in from the defaults of the selected
+/// call nature.
 #[derive(Debug, Serialize, Deserialize, Clone)]
+#[cfg_attr(feature = "codegen", derive(TS, JsonSchema))]
+pub struct NewIncidentCall {
+    pub name: String,
+    pub location_id: Option<LocationId>,
+    pub location_detail: Option<String>,
+    #[serde(default, with = "crate::wire::timestamp::option")]
+    #[cfg_attr(feature = "codegen", ts(as = "Option<String>"), schemars(with = "Option<String>"))]
+    pub dob: Option<OffsetDateTime>,
+    pub badge_number: Option<String>,
+    pub phone_number: String,
+    pub caller_name: String,
+    pub nature_code: String,
+    pub incident_type: Option<IncidentType>,
+    pub priority: Option<Priority>,
+}
+
+/// Changes a dispatcher makes to an open call. Fields left out keep their
+/// current value; the location ones are cleared by sending `null`.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[cfg_attr(feature = "codegen", derive(TS, JsonSchema))]
+#[serde(default)]
+pub struct CallUpdate {
+    pub name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none", with = "crate::wire::nullable")]
+    #[cfg_attr(feature = "codegen", ts(optional), schemars(with = "Option<LocationId>"))]
+    pub location_id: Option<Option<LocationId>>,
+    #[serde(skip_serializing_if = "Option::is_none", with = "crate::wire::nullable")]
+    #[cfg_attr(feature = "codegen", ts(optional), schemars(with = "Option<String>"))]
+    pub location_detail: Option<Option<String>>,
+    pub phone_number: Option<String>,
+    pub caller_name: Option<String>,
+    pub incident_type: Option<IncidentType>,
+    pub priority: Option<Priority>,
+    pub disposition: Option<Disposition>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[cfg_attr(feature = "codegen", derive(TS, JsonSchema))]
+#[cfg_attr(feature = "sqlx", derive(sqlx::Type))]
+#[cfg_attr(feature = "sqlx", sqlx(type_name = "incident_type", rename_all = "snake_case"))]
+#[serde(rename_all = "snake_case")]
 pub enum IncidentType {
     Security,
     Medical,
 }
 
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "codegen", derive(TS, JsonSchema))]
+#[cfg_attr(feature = "sqlx", derive(sqlx::Type))]
+#[cfg_attr(feature = "sqlx", sqlx(type_name = "priority", rename_all = "snake_case"))]
+#[serde(rename_all = "snake_case")]
+pub enum Priority {
+    High,
+    Medium,
+    Low,
+}
+
+/// An entry in the nature code table, e.g. "Chest pain" or "Disturbance".
 #[derive(Debug, Serialize, Deserialize, Clone)]
-pub enum CallNature {
-    ChiefComplaint,
-    SecurityComplaint,
+#[cfg_attr(feature = "codegen", derive(TS, JsonSchema))]
+pub struct CallNature {
+    pub code: String,
+    pub description: String,
+    pub default_priority: Priority,
+    pub default_incident_type: IncidentType,
+    pub recommended_unit_types: Vec<UnitType>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
+#[cfg_attr(feature = "codegen", derive(TS, JsonSchema))]
 pub struct Note {
-    pub id: String,
+    pub id: NoteId,
     pub author: String,
     pub content: String,
+    #[serde(with = "crate::wire::timestamp")]
+    #[cfg_attr(feature = "codegen", ts(as = "String"), schemars(with = "String"))]
     pub timestamp: OffsetDateTime,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[cfg_attr(feature = "codegen", derive(TS, JsonSchema))]
+#[cfg_attr(feature = "sqlx", derive(sqlx::Type))]
+#[cfg_attr(feature = "sqlx", sqlx(type_name = "disposition", rename_all = "snake_case"))]
+#[serde(rename_all = "snake_case")]
 pub enum Disposition {
     Resolved,
     Unresolved,
@@ -49,20 +131,56 @@ pub enum Disposition {
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
+#[cfg_attr(feature = "codegen", derive(TS, JsonSchema))]
 pub struct Unit {
-    pub id: String,
+    pub id: UnitId,
+    pub event_id: EventId,
     pub name: String,
     pub unit_type: UnitType,
     pub status: UnitStatus,
+    #[serde(with = "crate::wire::timestamp")]
+    #[cfg_attr(feature = "codegen", ts(as = "String"), schemars(with = "String"))]
+    pub status_since: OffsetDateTime,
+    #[serde(default, with = "crate::wire::timestamp::option")]
+    #[cfg_attr(feature = "codegen", ts(as = "Option<String>"), schemars(with = "Option<String>"))]
+    pub last_check_in: Option<OffsetDateTime>,
+    pub last_known_location: Option<String>,
+    /// Registered location the unit is at, used for routing
+    pub location_id: Option<LocationId>,
+    pub last_position: Option<Position>,
+    /// Set while the unit has an active emergency that has not been cleared
+    #[serde(default, with = "crate::wire::timestamp::option")]
+    #[cfg_attr(feature = "codegen", ts(as = "Option<String>"), schemars(with = "Option<String>"))]
+    pub emergency_since: Option<OffsetDateTime>,
 }
 
+/// A unit dispatched to an incident.
 #[derive(Debug, Serialize, Deserialize, Clone)]
+#[cfg_attr(feature = "codegen", derive(TS, JsonSchema))]
+pub struct AssignedUnit {
+    pub unit: Unit,
+    #[serde(with = "crate::wire::timestamp")]
+    #[cfg_attr(feature = "codegen", ts(as = "String"), schemars(with = "String"))]
+    pub assigned_at: OffsetDateTime,
+    /// Estimated walking time from where the unit was when dispatched
+    pub eta_secs: Option<u32>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[cfg_attr(feature = "codegen", derive(TS, JsonSchema))]
+#[cfg_attr(feature = "sqlx", derive(sqlx::Type))]
+#[cfg_attr(feature = "sqlx", sqlx(type_name = "unit_type", rename_all = "snake_case"))]
+#[serde(rename_all = "snake_case")]
 pub enum UnitType {
     Security,
     FirstAid,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "codegen", derive(TS, JsonSchema))]
+#[cfg_attr(feature = "sqlx", derive(sqlx::Type))]
+#[cfg_attr(feature = "sqlx", sqlx(type_name = "unit_status", rename_all = "snake_case"))]
+#[serde(rename_all = "snake_case")]
 pub enum UnitStatus {
     Available,
     Dispatched,
@@ -71,12 +189,31 @@ pub enum UnitStatus {
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
+#[cfg_attr(feature = "codegen", derive(TS, JsonSchema))]
 pub struct IncidentTimes {
+    #[serde(with = "crate::wire::timestamp")]
+    #[cfg_attr(feature = "codegen", ts(as = "String"), schemars(with = "String"))]
     pub received: OffsetDateTime,
+    #[serde(default, with = "crate::wire::timestamp::option")]
+    #[cfg_attr(feature = "codegen", ts(as = "Option<String>"), schemars(with = "Option<String>"))]
     pub assigned: Option<OffsetDateTime>,
+    #[serde(default, with = "crate::wire::timestamp::option")]
+    #[cfg_attr(feature = "codegen", ts(as = "Option<String>"), schemars(with = "Option<String>"))]
     pub responding: Option<OffsetDateTime>,
+    #[serde(default, with = "crate::wire::timestamp::option")]
+    #[cfg_attr(feature = "codegen", ts(as = "Option<String>"), schemars(with = "Option<String>"))]
     pub on_scene: Option<OffsetDateTime>,
+    #[serde(default, with = "crate::wire::timestamp::option")]
+    #[cfg_attr(feature = "codegen", ts(as = "Option<String>"), schemars(with = "Option<String>"))]
     pub transporting: Option<OffsetDateTime>,
+    #[serde(default, with = "crate::wire::timestamp::option")]
+    #[cfg_attr(feature = "codegen", ts(as = "Option<String>"), schemars(with = "Option<String>"))]
     pub at_destination: Option<OffsetDateTime>,
+    #[serde(default, with = "crate::wire::timestamp::option")]
+    #[cfg_attr(feature = "codegen", ts(as = "Option<String>"), schemars(with = "Option<String>"))]
     pub cleared: Option<OffsetDateTime>,
-}
\ No newline at end of file
+    /// When the call was escalated for waiting too long in the pending queue
+    #[serde(default, with = "crate::wire::timestamp::option")]
+    #[cfg_attr(feature = "codegen", ts(as = "Option<String>"), schemars(with = "Option<String>"))]
+    pub escalated: Option<OffsetDateTime>,
+}
@@ -1,24 +1,312 @@
+#[cfg(feature = "codegen")]
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
-mod incident;
+#[cfg(feature = "codegen")]
+use ts_rs::TS;
+pub mod accountability;
+#[cfg(feature = "codegen")]
+pub mod codegen;
+pub mod encoding;
+pub mod event;
+pub mod id;
+pub mod incident;
+pub mod location;
+pub mod map;
+pub mod presence;
+pub mod routing;
+pub mod wire;
 
-/// Protocol messages that can be sent in either direction
+use accountability::{RollCall, RollCallEntry};
+use event::{Event, NewEvent};
+use id::{EventId, IncidentId, LocationId, RollCallId, UnitId};
+use incident::{CallNature, CallUpdate, IncidentCall, NewIncidentCall, Priority, Unit, UnitStatus};
+use location::{Location, NewLocation};
+use map::{MapLayer, Position};
+use presence::OnlineDispatcher;
+use routing::{LocationLink, NewLocationLink, Route, UnitRecommendation};
+use time::Date;
+
+/// Version of the protocol defined by this crate. Bump it whenever a change
+/// would break peers built against an older version.
+pub const PROTOCOL_VERSION: u32 = 2;
+
+/// Oldest protocol version the server accepts. For now this is the current
+/// version only: serving an older version alongside it is not supported, so the
+/// server and every console must be upgraded together. Version 1 clients
+/// predate the `Hello` handshake and are turned away with `UnsupportedVersion`.
+pub const MIN_PROTOCOL_VERSION: u32 = 2;
+
+/// Protocol messages that can be sent in either direction.
+///
+/// A client opens with `Hello`; the server answers `Welcome` with the version
+/// both sides will speak, or `UnsupportedVersion` before closing the connection.
+///
+/// Requests that read or create event data act on the console's active event,
+/// chosen with `SetActiveEvent`.
+///
+/// On the wire every message travels in an [`Envelope`].
 #[derive(Debug, Serialize, Deserialize, Clone)]
+#[cfg_attr(feature = "codegen", derive(TS, JsonSchema))]
 #[serde(tag = "type", content = "payload")]
 pub enum ProtocolMessage {
+    #[serde(rename = "hello")]
+    Hello {
+        /// Oldest and newest protocol versions the client can speak
+        min_version: u32,
+        max_version: u32,
+        /// Client name and version, for the server's logs
+        client: String,
+        /// Optional features the client would like enabled
+        capabilities: Vec<String>,
+    },
+    #[serde(rename = "welcome")]
+    Welcome {
+        /// Version both sides will speak for the rest of the connection
+        version: u32,
+        server: String,
+        /// Requested capabilities the server has enabled
+        capabilities: Vec<String>,
+    },
+    #[serde(rename = "unsupported_version")]
+    UnsupportedVersion {
+        min_version: u32,
+        max_version: u32,
+        /// Human-readable explanation telling the user what to upgrade
+        message: String,
+    },
     #[serde(rename = "ping")]
     Ping,
+    #[serde(rename = "list_events")]
+    ListEvents,
+    #[serde(rename = "events")]
+    Events { events: Vec<Event> },
+    /// Manager only
+    #[serde(rename = "create_event")]
+    CreateEvent { event: NewEvent },
+    #[serde(rename = "event")]
+    Event { event: Event },
+    #[serde(rename = "set_active_event")]
+    SetActiveEvent { event_id: EventId },
+    #[serde(rename = "active_event")]
+    ActiveEvent { event: Event },
     #[serde(rename = "get_active_calls")]
     GetActiveCalls,
+    #[serde(rename = "active_calls")]
+    ActiveCalls { calls: Vec<IncidentCall> },
+    /// Every call in an event; managers only unless it is the active event
+    #[serde(rename = "get_event_calls")]
+    GetEventCalls { event_id: EventId },
+    #[serde(rename = "event_calls")]
+    EventCalls { event_id: EventId, calls: Vec<IncidentCall> },
+    /// Calls received on a calendar day of the active event, in event-local time
+    #[serde(rename = "get_calls_on_day")]
+    GetCallsOnDay {
+        #[serde(with = "wire::date")]
+        #[cfg_attr(feature = "codegen", ts(as = "String"), schemars(with = "String"))]
+        date: Date,
+    },
+    #[serde(rename = "calls_on_day")]
+    CallsOnDay {
+        #[serde(with = "wire::date")]
+        #[cfg_attr(feature = "codegen", ts(as = "String"), schemars(with = "String"))]
+        date: Date,
+        calls: Vec<IncidentCall>,
+    },
     #[serde(rename = "get_call")]
-    GetCall { id: String },
+    GetCall { id: IncidentId },
     #[serde(rename = "create_call")]
-    CreateCall,
+    CreateCall { call: NewIncidentCall },
+    /// Changes the fields set in `update`; replied to with the updated `Call`
     #[serde(rename = "update_call")]
-    UpdateCall { id: String },
+    UpdateCall { id: IncidentId, update: CallUpdate },
+    /// Replied to with the `Call` including the new note
+    #[serde(rename = "add_note")]
+    AddNote { incident_id: IncidentId, content: String },
+    #[serde(rename = "get_call_natures")]
+    GetCallNatures,
+    #[serde(rename = "call_natures")]
+    CallNatures { natures: Vec<CallNature> },
+    #[serde(rename = "call")]
+    Call { call: IncidentCall },
+    #[serde(rename = "get_pending_queue")]
+    GetPendingQueue,
+    /// Unassigned calls, highest priority first and longest waiting first within a priority
+    #[serde(rename = "pending_queue")]
+    PendingQueue { calls: Vec<IncidentCall> },
+    /// Broadcast when a pending call has waited past its priority's threshold
+    #[serde(rename = "call_escalated")]
+    CallEscalated {
+        id: IncidentId,
+        priority: Priority,
+        #[cfg_attr(feature = "codegen", ts(type = "number"))]
+        waiting_secs: i64,
+    },
+    #[serde(rename = "get_units")]
+    GetUnits,
+    #[serde(rename = "units")]
+    Units { units: Vec<Unit> },
+    #[serde(rename = "update_unit_status")]
+    UpdateUnitStatus { unit_id: UnitId, status: UnitStatus, location: Option<String> },
+    /// Broadcast whenever a unit changes status or checks in
+    #[serde(rename = "unit_updated")]
+    UnitUpdated { unit: Unit },
+    /// Dispatcher acknowledgement of a unit's welfare; resets its status timer
+    #[serde(rename = "check_in_unit")]
+    CheckInUnit { unit_id: UnitId },
+    /// Broadcast when a unit has sat in a timed status past its threshold
+    #[serde(rename = "unit_overdue")]
+    UnitOverdue {
+        unit_id: UnitId,
+        status: UnitStatus,
+        #[cfg_attr(feature = "codegen", ts(type = "number"))]
+        elapsed_secs: i64,
+    },
+    /// Sent by a unit in distress; `location` overrides its last known location
+    #[serde(rename = "activate_emergency")]
+    ActivateEmergency { unit_id: UnitId, location: Option<String> },
+    /// Broadcast to every console; clients should sound an alert when `audible` is set
+    #[serde(rename = "emergency_alert")]
+    EmergencyAlert { unit: Unit, incident_id: IncidentId, location: String, audible: bool },
+    #[serde(rename = "acknowledge_emergency")]
+    AcknowledgeEmergency { unit_id: UnitId },
+    #[serde(rename = "emergency_acknowledged")]
+    EmergencyAcknowledged { unit_id: UnitId, acknowledged_by: String },
+    #[serde(rename = "clear_emergency")]
+    ClearEmergency { unit_id: UnitId },
+    #[serde(rename = "emergency_cleared")]
+    EmergencyCleared { unit_id: UnitId, cleared_by: String },
+    /// Supervisor only; snapshots every on-duty unit into a new roll call
+    #[serde(rename = "start_roll_call")]
+    StartRollCall { reason: String },
+    #[serde(rename = "mark_unit_accounted")]
+    MarkUnitAccounted { roll_call_id: RollCallId, unit_id: UnitId },
+    /// Supervisor only; re-sends the alert to units still outstanding
+    #[serde(rename = "realert_roll_call")]
+    RealertRollCall { roll_call_id: RollCallId },
+    #[serde(rename = "complete_roll_call")]
+    CompleteRollCall { roll_call_id: RollCallId },
+    #[serde(rename = "get_roll_calls")]
+    GetRollCalls,
+    #[serde(rename = "roll_calls")]
+    RollCalls { roll_calls: Vec<RollCall> },
+    /// Broadcast whenever a roll call starts, a unit is accounted for or it completes
+    #[serde(rename = "roll_call_updated")]
+    RollCallUpdated { roll_call: RollCall },
+    /// Broadcast to prompt the listed units to report in
+    #[serde(rename = "roll_call_alert")]
+    RollCallAlert { roll_call_id: RollCallId, outstanding: Vec<RollCallEntry> },
+    #[serde(rename = "get_locations")]
+    GetLocations,
+    #[serde(rename = "locations")]
+    Locations { locations: Vec<Location> },
+    /// Autocomplete a typed location against the event's registry and aliases
+    #[serde(rename = "resolve_location")]
+    ResolveLocation { query: String },
+    #[serde(rename = "location_matches")]
+    LocationMatches { query: String, matches: Vec<Location> },
+    /// Supervisor only
+    #[serde(rename = "create_location")]
+    CreateLocation { location: NewLocation },
+    #[serde(rename = "location")]
+    Location { location: Location },
+    /// Admin only; `geojson` must be a `FeatureCollection`. Point features whose
+    /// `location_id` or `name` property matches a registered location set its coordinates.
+    #[serde(rename = "import_map_layer")]
+    ImportMapLayer { name: String, geojson: serde_json::Value },
+    #[serde(rename = "map_layer_imported")]
+    MapLayerImported { name: String, located: usize },
+    /// Supervisor only
+    #[serde(rename = "set_location_position")]
+    SetLocationPosition { location_id: LocationId, position: Position },
+    #[serde(rename = "update_unit_position")]
+    UpdateUnitPosition { unit_id: UnitId, position: Position },
+    /// Imported venue layers for the event plus live unit and incident layers
+    #[serde(rename = "get_map_layers")]
+    GetMapLayers,
+    #[serde(rename = "map_layers")]
+    MapLayers { layers: Vec<MapLayer> },
+    /// Supervisor only
+    #[serde(rename = "create_location_link")]
+    CreateLocationLink { link: NewLocationLink },
+    #[serde(rename = "get_location_links")]
+    GetLocationLinks,
+    #[serde(rename = "location_links")]
+    LocationLinks { links: Vec<LocationLink> },
+    #[serde(rename = "get_route")]
+    GetRoute { from_location_id: LocationId, to_location_id: LocationId, accessible_only: bool },
+    /// `route` is `None` when the locations are not connected
+    #[serde(rename = "route")]
+    Route { route: Option<Route> },
+    #[serde(rename = "update_unit_location")]
+    UpdateUnitLocation { unit_id: UnitId, location_id: LocationId },
+    /// Available units of the call nature's recommended types, nearest first
+    #[serde(rename = "recommend_units")]
+    RecommendUnits { incident_id: IncidentId, accessible_only: bool },
+    #[serde(rename = "unit_recommendations")]
+    UnitRecommendations { incident_id: IncidentId, recommendations: Vec<UnitRecommendation> },
+    #[serde(rename = "dispatch_unit")]
+    DispatchUnit { incident_id: IncidentId, unit_id: UnitId },
+    /// Broadcast whenever an incident changes
+    #[serde(rename = "call_updated")]
+    CallUpdated { call: IncidentCall },
+    /// Broadcast to an event's consoles whenever one joins or leaves it
+    #[serde(rename = "presence_changed")]
+    PresenceChanged { event_id: EventId, online: Vec<OnlineDispatcher> },
+    /// Reply to a request that has no other result, sent only when the request
+    /// carried a `request_id`
+    #[serde(rename = "ack")]
+    Ack,
+    #[serde(rename = "error")]
+    Error { message: String },
+    #[serde(rename = "text")]
     Text(String),
+    #[serde(rename = "json")]
     Json(String),
 }
 
+/// A `ProtocolMessage` with an optional request ID alongside its `type` and
+/// `payload`.
+///
+/// The server copies a request's `request_id` onto its direct reply, including
+/// `Error` and `Ack`, so a client can match replies to requests while broadcasts
+/// arrive in between. Broadcasts and replies to requests without an ID carry none.
+///
+/// ts-rs would inline every message into a flattened field, so the TypeScript
+/// declaration is written by hand in `codegen`.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[cfg_attr(feature = "codegen", derive(JsonSchema))]
+pub struct Envelope {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub request_id: Option<u32>,
+    #[serde(flatten)]
+    pub message: ProtocolMessage,
+}
+
+impl ProtocolMessage {
+    /// The wire name of the message, e.g. `create_call`.
+    pub fn message_type(&self) -> String {
+        serde_json::to_value(self)
+            .ok()
+            .and_then(|value| value.get("type")?.as_str().map(str::to_string))
+            .unwrap_or_default()
+    }
+}
+
+impl Envelope {
+    /// A reply to the request that carried `request_id`.
+    pub fn reply(request_id: Option<u32>, message: ProtocolMessage) -> Self {
+        Self { request_id, message }
+    }
+}
+
+impl From<ProtocolMessage> for Envelope {
+    fn from(message: ProtocolMessage) -> Self {
+        Self {
+            request_id: None,
+            message,
+        }
+    }
+}
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Call {
@@ -0,0 +1,57 @@
+#[cfg(feature = "codegen")]
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "codegen")]
+use ts_rs::TS;
+
+use crate::id::{EventId, LocationId};
+use crate::map::Position;
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "codegen", derive(TS, JsonSchema))]
+#[cfg_attr(feature = "sqlx", derive(sqlx::Type))]
+#[cfg_attr(feature = "sqlx", sqlx(type_name = "location_kind", rename_all = "snake_case"))]
+#[serde(rename_all = "snake_case")]
+pub enum LocationKind {
+    Building,
+    Floor,
+    Room,
+    OutdoorZone,
+    /// A fixed staffed post such as a door or info desk
+    Post,
+}
+
+/// A registered place within an event's venue, e.g. "Ballroom B".
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[cfg_attr(feature = "codegen", derive(TS, JsonSchema))]
+pub struct Location {
+    pub id: LocationId,
+    pub event_id: EventId,
+    pub name: String,
+    pub kind: LocationKind,
+    /// The building or floor this location sits within
+    pub parent_id: Option<LocationId>,
+    /// Other names dispatchers use for the same place, e.g. "BR-B"
+    pub aliases: Vec<String>,
+    pub position: Option<Position>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[cfg_attr(feature = "codegen", derive(TS, JsonSchema))]
+pub struct NewLocation {
+    pub name: String,
+    pub kind: LocationKind,
+    pub parent_id: Option<LocationId>,
+    pub aliases: Vec<String>,
+}
+
+/// Where an incident is, as a registered location plus optional free-text detail.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[cfg_attr(feature = "codegen", derive(TS, JsonSchema))]
+pub struct IncidentLocation {
+    pub location_id: Option<LocationId>,
+    /// Name of the registered location, filled in by the server
+    pub name: Option<String>,
+    /// Free-text detail such as "by the east doors"
+    pub detail: Option<String>,
+}
@@ -0,0 +1,34 @@
+#[cfg(feature = "codegen")]
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "codegen")]
+use ts_rs::TS;
+
+/// A WGS84 point, as used by GeoJSON.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "codegen", derive(TS, JsonSchema))]
+pub struct Position {
+    pub longitude: f64,
+    pub latitude: f64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "codegen", derive(TS, JsonSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum MapLayerKind {
+    /// Imported floorplan or site map
+    Venue,
+    /// Generated by the server from current unit positions
+    Units,
+    /// Generated by the server from open incidents at located places
+    Incidents,
+}
+
+/// A named GeoJSON `FeatureCollection` the client can draw.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[cfg_attr(feature = "codegen", derive(TS, JsonSchema))]
+pub struct MapLayer {
+    pub name: String,
+    pub kind: MapLayerKind,
+    pub geojson: serde_json::Value,
+}
@@ -0,0 +1,18 @@
+#[cfg(feature = "codegen")]
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "codegen")]
+use ts_rs::TS;
+
+use crate::id::UserId;
+
+/// A dispatcher with at least one console open on an event.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[cfg_attr(feature = "codegen", derive(TS, JsonSchema))]
+pub struct OnlineDispatcher {
+    pub user_id: UserId,
+    /// Display name, or email for users without one
+    pub name: String,
+    /// Consoles they have open on the event
+    pub consoles: u32,
+}
@@ -0,0 +1,67 @@
+#[cfg(feature = "codegen")]
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "codegen")]
+use ts_rs::TS;
+
+use crate::id::{EventId, LocationId, LocationLinkId};
+use crate::incident::Unit;
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "codegen", derive(TS, JsonSchema))]
+#[cfg_attr(feature = "sqlx", derive(sqlx::Type))]
+#[cfg_attr(feature = "sqlx", sqlx(type_name = "link_kind", rename_all = "snake_case"))]
+#[serde(rename_all = "snake_case")]
+pub enum LinkKind {
+    Hallway,
+    Elevator,
+    Stairs,
+    Escalator,
+    Outdoor,
+}
+
+/// A walkable connection between two registered locations.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[cfg_attr(feature = "codegen", derive(TS, JsonSchema))]
+pub struct LocationLink {
+    pub id: LocationLinkId,
+    pub event_id: EventId,
+    pub from_location_id: LocationId,
+    pub to_location_id: LocationId,
+    pub kind: LinkKind,
+    /// Typical walking time in seconds
+    pub travel_secs: u32,
+    /// Usable with a wheelchair or stretcher
+    pub accessible: bool,
+    /// `false` for one-way links such as escalators
+    pub bidirectional: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[cfg_attr(feature = "codegen", derive(TS, JsonSchema))]
+pub struct NewLocationLink {
+    pub from_location_id: LocationId,
+    pub to_location_id: LocationId,
+    pub kind: LinkKind,
+    pub travel_secs: u32,
+    pub accessible: bool,
+    pub bidirectional: bool,
+}
+
+/// Shortest walk between two locations.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[cfg_attr(feature = "codegen", derive(TS, JsonSchema))]
+pub struct Route {
+    /// Locations visited in order, including both ends
+    pub location_ids: Vec<LocationId>,
+    pub eta_secs: u32,
+}
+
+/// An available unit suggested for an incident.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[cfg_attr(feature = "codegen", derive(TS, JsonSchema))]
+pub struct UnitRecommendation {
+    pub unit: Unit,
+    /// `None` when the unit's location is unknown or unreachable
+    pub route: Option<Route>,
+}
@@ -0,0 +1,52 @@
+//! Canonical JSON encodings for values whose serde defaults are not portable.
+//!
+//! The wire format every client and server must agree on:
+//!
+//! - Timestamps are RFC 3339 strings, e.g. `"2024-11-03T06:30:00Z"`; fields use
+//!   `#[serde(with = "crate::wire::timestamp")]` (or `timestamp::option`).
+//! - Calendar dates are `"YYYY-MM-DD"`, via `#[serde(with = "crate::wire::date")]`.
+//! - Enum values and `ProtocolMessage` tags are snake_case.
+//! - Update fields that can be cleared are left out to keep their value and
+//!   `null` to clear it, via `#[serde(with = "crate::wire::nullable")]`.
+//!
+//! `tests/wire_format.rs` pins the format with golden files.
+
+pub use time::serde::rfc3339 as timestamp;
+
+/// `time::Date` as `"YYYY-MM-DD"`.
+pub mod date {
+    use serde::{de::Error, Deserialize, Deserializer, Serializer};
+    use time::{format_description::BorrowedFormatItem, macros::format_description, Date};
+
+    const FORMAT: &[BorrowedFormatItem<'static>] = format_description!("[year]-[month]-[day]");
+
+    pub fn serialize<S: Serializer>(date: &Date, serializer: S) -> Result<S::Ok, S::Error> {
+        let formatted = date.format(FORMAT).map_err(serde::ser::Error::custom)?;
+        serializer.serialize_str(&formatted)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Date, D::Error> {
+        let formatted = String::deserialize(deserializer)?;
+        Date::parse(&formatted, FORMAT).map_err(D::Error::custom)
+    }
+}
+
+/// `Option<Option<T>>` for an update field that can be left alone (missing),
+/// cleared (`null`) or set. Fields also need `default` and
+/// `skip_serializing_if = "Option::is_none"`.
+pub mod nullable {
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    pub fn serialize<S: Serializer, T: Serialize>(value: &Option<Option<T>>, serializer: S) -> Result<S::Ok, S::Error> {
+        match value {
+            Some(value) => value.serialize(serializer),
+            None => serializer.serialize_none(),
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>, T: Deserialize<'de>>(
+        deserializer: D,
+    ) -> Result<Option<Option<T>>, D::Error> {
+        Option::<T>::deserialize(deserializer).map(Some)
+    }
+}
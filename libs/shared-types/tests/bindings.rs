@@ -0,0 +1,32 @@
+//! Fails when the checked-in TypeScript and JSON Schema bindings no longer
+//! match the Rust types. Run with `UPDATE_BINDINGS=1` to regenerate them.
+
+use std::{fs, path::PathBuf};
+
+use shared_types::codegen;
+
+fn check(file: &str, generated: String) {
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("bindings").join(file);
+    if std::env::var_os("UPDATE_BINDINGS").is_some() {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, generated).unwrap();
+        return;
+    }
+
+    let checked_in = fs::read_to_string(&path).unwrap_or_else(|e| panic!("reading {}: {e}", path.display()));
+    assert!(
+        checked_in == generated,
+        "{} is out of date; regenerate with UPDATE_BINDINGS=1 cargo test -p shared-types",
+        path.display()
+    );
+}
+
+#[test]
+fn typescript_bindings_are_current() {
+    check("protocol.ts", codegen::typescript());
+}
+
+#[test]
+fn json_schema_is_current() {
+    check("protocol.schema.json", codegen::json_schema());
+}
@@ -0,0 +1,80 @@
+//! Event-local day boundaries and rendering across DST changes.
+
+use shared_types::event::Event;
+use time::macros::{date, datetime};
+use time::Duration;
+
+fn event(time_zone: &str) -> Event {
+    Event {
+        id: "a3f1c2d4-1111-4b6a-8e9f-0123456789ab".parse().unwrap(),
+        name: "Test Con".to_string(),
+        starts_on: date!(2024 - 03 - 08),
+        ends_on: date!(2024 - 11 - 04),
+        time_zone: time_zone.to_string(),
+        settings: serde_json::json!({}),
+    }
+}
+
+#[test]
+fn ordinary_day_is_24_hours_from_local_midnight() {
+    let event = event("America/Chicago");
+    let (start, end) = event.day_bounds(date!(2024 - 07 - 13));
+    assert_eq!(start, datetime!(2024-07-13 05:00 UTC));
+    assert_eq!(end - start, Duration::hours(24));
+}
+
+#[test]
+fn spring_forward_day_is_23_hours() {
+    let event = event("America/Chicago");
+    let (start, end) = event.day_bounds(date!(2024 - 03 - 10));
+    assert_eq!(start, datetime!(2024-03-10 06:00 UTC));
+    assert_eq!(end, datetime!(2024-03-11 05:00 UTC));
+    assert_eq!(end - start, Duration::hours(23));
+}
+
+#[test]
+fn fall_back_day_is_25_hours() {
+    let event = event("America/Chicago");
+    let (start, end) = event.day_bounds(date!(2024 - 11 - 03));
+    assert_eq!(start, datetime!(2024-11-03 05:00 UTC));
+    assert_eq!(end, datetime!(2024-11-04 06:00 UTC));
+    assert_eq!(end - start, Duration::hours(25));
+}
+
+#[test]
+fn midnight_in_a_dst_gap_starts_the_day_after_the_gap() {
+    // Havana springs forward at midnight, so 00:00 on that day never happens
+    let event = event("America/Havana");
+    let start = event.start_of_day(date!(2024 - 03 - 10));
+    assert_eq!(start, datetime!(2024-03-10 05:00 UTC));
+    assert_eq!(event.local_time(start).time(), time::macros::time!(01:00));
+}
+
+#[test]
+fn instants_near_midnight_fall_on_the_local_day() {
+    let event = event("America/Chicago");
+    // 03:30 UTC on the 10th is still the evening of the 9th in Chicago
+    assert_eq!(event.local_date(datetime!(2024-03-10 03:30 UTC)), date!(2024 - 03 - 09));
+    assert!(event.is_running_at(datetime!(2024-03-09 03:30 UTC)));
+    assert!(!event.is_running_at(datetime!(2024-03-08 05:59 UTC)));
+}
+
+#[test]
+fn repeated_hour_renders_with_each_offset() {
+    let event = event("America/Chicago");
+    assert_eq!(
+        event.format_local(datetime!(2024-11-03 06:30 UTC)),
+        "2024-11-03T01:30:00-05:00 CDT"
+    );
+    assert_eq!(
+        event.format_local(datetime!(2024-11-03 07:30 UTC)),
+        "2024-11-03T01:30:00-06:00 CST"
+    );
+}
+
+#[test]
+fn last_day_ends_at_the_end_of_time() {
+    let event = event("UTC");
+    let (start, end) = event.day_bounds(time::Date::MAX);
+    assert!(end > start);
+}
@@ -0,0 +1,298 @@
+//! Golden-file tests for the JSON wire format.
+//!
+//! Each sample message is serialized and compared byte-for-byte with
+//! `tests/golden/<name>.json`, then the golden file is parsed back and
+//! re-serialized to check the round trip. Run with `UPDATE_GOLDEN=1` to rewrite
+//! the golden files after an intentional format change.
+
+use std::io::Write;
+use std::{fs, path::PathBuf};
+
+use flate2::{write::DeflateEncoder, Compression};
+use shared_types::encoding::{self, Encoding, Frame, MAX_FRAME_BYTES};
+use shared_types::event::Event;
+use shared_types::incident::{
+    AssignedUnit, CallNature, CallUpdate, Disposition, IncidentCall, IncidentTimes, IncidentType, Note, Priority, Unit,
+    UnitStatus, UnitType,
+};
+use shared_types::location::IncidentLocation;
+use shared_types::map::Position;
+use shared_types::presence::OnlineDispatcher;
+use shared_types::{Envelope, ProtocolMessage};
+use time::macros::{date, datetime};
+
+fn unit() -> Unit {
+    Unit {
+        id: "5b0c8f9e-2f55-4f4e-9a53-8c1f0a6d2e11".parse().unwrap(),
+        event_id: "a3f1c2d4-1111-4b6a-8e9f-0123456789ab".parse().unwrap(),
+        name: "Medic 1".to_string(),
+        unit_type: UnitType::FirstAid,
+        status: UnitStatus::OnScene,
+        status_since: datetime!(2024-11-03 06:45:00 UTC),
+        last_check_in: None,
+        last_known_location: Some("Ballroom B".to_string()),
+        location_id: None,
+        last_position: Some(Position {
+            longitude: -97.7431,
+            latitude: 30.2672,
+        }),
+        emergency_since: None,
+    }
+}
+
+fn call() -> IncidentCall {
+    IncidentCall {
+        id: "0d4f6a1e-7c2b-4d8e-b1a9-5e3c2f1d0a99".parse().unwrap(),
+        incident_number: "24-000123".to_string(),
+        event_id: "a3f1c2d4-1111-4b6a-8e9f-0123456789ab".parse().unwrap(),
+        date_of_service: datetime!(2024-11-03 06:30:00 UTC),
+        name: "Jane Doe".to_string(),
+        location: IncidentLocation {
+            location_id: Some("9c7e5a3b-2d1f-4e6a-8b0c-1a2b3c4d5e6f".parse().unwrap()),
+            name: Some("Ballroom B".to_string()),
+            detail: Some("Near the stage".to_string()),
+        },
+        dob: None,
+        badge_number: Some("1234".to_string()),
+        phone_number: "555-0100".to_string(),
+        caller_name: "Front desk".to_string(),
+        incident_type: IncidentType::Medical,
+        priority: Priority::High,
+        call_nature: CallNature {
+            code: "CHEST".to_string(),
+            description: "Chest pain".to_string(),
+            default_priority: Priority::High,
+            default_incident_type: IncidentType::Medical,
+            recommended_unit_types: vec![UnitType::FirstAid],
+        },
+        notes: vec![Note {
+            id: "7e1d2c3b-4a5f-4e6d-9c8b-7a6f5e4d3c2b".parse().unwrap(),
+            author: "dispatch@example.org".to_string(),
+            content: "Patient conscious and breathing".to_string(),
+            timestamp: datetime!(2024-11-03 01:31:00 -5),
+        }],
+        disposition: Disposition::Pending,
+        units_assigned: vec![AssignedUnit {
+            unit: unit(),
+            assigned_at: datetime!(2024-11-03 06:32:15.5 UTC),
+            eta_secs: Some(90),
+        }],
+        times: IncidentTimes {
+            received: datetime!(2024-11-03 06:30:00 UTC),
+            assigned: Some(datetime!(2024-11-03 06:32:15.5 UTC)),
+            responding: None,
+            on_scene: Some(datetime!(2024-11-03 06:45:00 UTC)),
+            transporting: None,
+            at_destination: None,
+            cleared: None,
+            escalated: None,
+        },
+    }
+}
+
+fn samples() -> Vec<(&'static str, ProtocolMessage)> {
+    vec![
+        (
+            "hello",
+            ProtocolMessage::Hello {
+                min_version: 1,
+                max_version: 2,
+                client: "rover/0.3.0".to_string(),
+                capabilities: Vec::new(),
+            },
+        ),
+        ("ping", ProtocolMessage::Ping),
+        ("call", ProtocolMessage::Call { call: call() }),
+        ("unit_updated", ProtocolMessage::UnitUpdated { unit: unit() }),
+        (
+            "active_event",
+            ProtocolMessage::ActiveEvent {
+                event: Event {
+                    id: "a3f1c2d4-1111-4b6a-8e9f-0123456789ab".parse().unwrap(),
+                    name: "FurCon 2024".to_string(),
+                    starts_on: date!(2024 - 11 - 01),
+                    ends_on: date!(2024 - 11 - 03),
+                    time_zone: "America/Chicago".to_string(),
+                    settings: serde_json::json!({ "call_number_prefix": "24" }),
+                },
+            },
+        ),
+        (
+            "get_calls_on_day",
+            ProtocolMessage::GetCallsOnDay {
+                date: date!(2024 - 11 - 03),
+            },
+        ),
+        (
+            "update_unit_status",
+            ProtocolMessage::UpdateUnitStatus {
+                unit_id: "5b0c8f9e-2f55-4f4e-9a53-8c1f0a6d2e11".parse().unwrap(),
+                status: UnitStatus::Available,
+                location: None,
+            },
+        ),
+        (
+            "call_escalated",
+            ProtocolMessage::CallEscalated {
+                id: "0d4f6a1e-7c2b-4d8e-b1a9-5e3c2f1d0a99".parse().unwrap(),
+                priority: Priority::Medium,
+                waiting_secs: 300,
+            },
+        ),
+        (
+            "update_call",
+            ProtocolMessage::UpdateCall {
+                id: "0d4f6a1e-7c2b-4d8e-b1a9-5e3c2f1d0a99".parse().unwrap(),
+                update: CallUpdate {
+                    location_detail: Some(None),
+                    priority: Some(Priority::High),
+                    disposition: Some(Disposition::Resolved),
+                    ..Default::default()
+                },
+            },
+        ),
+        (
+            "add_note",
+            ProtocolMessage::AddNote {
+                incident_id: "0d4f6a1e-7c2b-4d8e-b1a9-5e3c2f1d0a99".parse().unwrap(),
+                content: "Patient conscious and breathing".to_string(),
+            },
+        ),
+        (
+            "presence_changed",
+            ProtocolMessage::PresenceChanged {
+                event_id: "a3f1c2d4-1111-4b6a-8e9f-0123456789ab".parse().unwrap(),
+                online: vec![OnlineDispatcher {
+                    user_id: "7e1d2c3b-4a59-4687-9a0b-1c2d3e4f5a6b".parse().unwrap(),
+                    name: "Sam Rivera".to_string(),
+                    consoles: 2,
+                }],
+            },
+        ),
+        ("text", ProtocolMessage::Text("hello".to_string())),
+        (
+            "error",
+            ProtocolMessage::Error {
+                message: "No active event selected".to_string(),
+            },
+        ),
+    ]
+}
+
+fn golden_path(name: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/golden")
+        .join(format!("{name}.json"))
+}
+
+#[test]
+fn messages_match_golden_files() {
+    let update = std::env::var_os("UPDATE_GOLDEN").is_some();
+
+    for (name, message) in samples() {
+        let json = serde_json::to_string_pretty(&message).unwrap() + "\n";
+        let path = golden_path(name);
+        if update {
+            fs::write(&path, &json).unwrap();
+            continue;
+        }
+
+        let golden = fs::read_to_string(&path).unwrap_or_else(|e| panic!("reading {}: {e}", path.display()));
+        assert_eq!(json, golden, "{name} does not match {}", path.display());
+    }
+}
+
+#[test]
+fn golden_files_round_trip() {
+    for (name, _) in samples() {
+        let golden = fs::read_to_string(golden_path(name)).unwrap();
+        let message: ProtocolMessage =
+            serde_json::from_str(&golden).unwrap_or_else(|e| panic!("parsing {name}: {e}"));
+        let json = serde_json::to_string_pretty(&message).unwrap() + "\n";
+        assert_eq!(json, golden, "{name} changed after a round trip");
+    }
+}
+
+#[test]
+fn updates_tell_clearing_from_leaving_alone() {
+    let update: CallUpdate = serde_json::from_str(r#"{"location_id":null,"priority":"low"}"#).unwrap();
+    assert_eq!(update.location_id, Some(None));
+    assert_eq!(update.location_detail, None);
+
+    let json = serde_json::to_value(&update).unwrap();
+    assert_eq!(json["location_id"], serde_json::Value::Null);
+    assert!(json.get("location_detail").is_none());
+}
+
+#[test]
+fn timestamps_keep_their_offset() {
+    let note: Note = serde_json::from_str(
+        r#"{"id":"7e1d2c3b-4a5f-4e6d-9c8b-7a6f5e4d3c2b","author":"a","content":"c","timestamp":"2024-11-03T01:31:00-05:00"}"#,
+    )
+    .unwrap();
+    assert_eq!(note.timestamp, datetime!(2024-11-03 06:31:00 UTC));
+    assert_eq!(note.timestamp.offset().whole_hours(), -5);
+}
+
+#[test]
+fn binary_encodings_round_trip() {
+    let encodings = [
+        Encoding { msgpack: true, deflate: false },
+        Encoding { msgpack: false, deflate: true },
+        Encoding { msgpack: true, deflate: true },
+    ];
+    for encoding in encodings {
+        for (name, message) in samples() {
+            let message = Envelope::reply(Some(7), message);
+            let Frame::Binary(frame) = encoding.encode(&message).unwrap() else {
+                panic!("{name} was not sent as binary with {encoding:?}");
+            };
+            let decoded: Envelope = encoding
+                .decode_binary(&frame)
+                .unwrap_or_else(|e| panic!("decoding {name} with {encoding:?}: {e}"));
+            assert_eq!(
+                serde_json::to_value(&decoded).unwrap(),
+                serde_json::to_value(&message).unwrap(),
+                "{name} changed after a {encoding:?} round trip"
+            );
+        }
+    }
+}
+
+#[test]
+fn oversized_deflated_frames_are_refused() {
+    // A few KB on the wire that would inflate to just past the limit
+    let mut encoder = DeflateEncoder::new(Vec::new(), Compression::best());
+    encoder.write_all(&vec![b' '; MAX_FRAME_BYTES as usize + 1]).unwrap();
+    let frame = encoder.finish().unwrap();
+    assert!(frame.len() < 64 * 1024);
+
+    let encoding = Encoding { msgpack: false, deflate: true };
+    let result = encoding.decode_binary::<Envelope>(&frame);
+    assert!(matches!(result, Err(encoding::Error::TooLarge)), "{result:?}");
+}
+
+#[test]
+fn envelopes_carry_request_ids() {
+    let request = Envelope::reply(Some(42), ProtocolMessage::GetUnits);
+    let json = serde_json::to_value(&request).unwrap();
+    assert_eq!(json, serde_json::json!({ "request_id": 42, "type": "get_units" }));
+
+    // Messages from clients that predate request IDs are still accepted...
+    let bare: Envelope = serde_json::from_str(r#"{"type":"ping"}"#).unwrap();
+    assert_eq!(bare.request_id, None);
+    assert!(matches!(bare.message, ProtocolMessage::Ping));
+    assert!(!serde_json::to_string(&bare).unwrap().contains("request_id"));
+
+    // ...and they can still read a message that carries one
+    let reply = r#"{"request_id":3,"type":"error","payload":{"message":"nope"}}"#;
+    let message: ProtocolMessage = serde_json::from_str(reply).unwrap();
+    assert!(matches!(message, ProtocolMessage::Error { .. }));
+}
+
+#[test]
+fn malformed_ids_are_rejected() {
+    let result = serde_json::from_str::<ProtocolMessage>(r#"{"type":"get_call","payload":{"id":"n1"}}"#);
+    let error = result.unwrap_err().to_string();
+    assert!(error.contains("invalid incident ID"), "{error}");
+}